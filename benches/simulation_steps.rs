@@ -0,0 +1,56 @@
+//! Measures how many environment steps per second a generation plays.
+//!
+//! Run with `cargo bench --bench simulation_steps -- [population] [workers]`.
+
+use std::{env, num::NonZero, sync::atomic::Ordering, time::Instant};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    multi_threading::{PopulationFiles, SharedResources},
+    trainer,
+    worker_pool::WorkerPool,
+};
+
+const GENERATIONS: usize = 3;
+
+fn main() {
+    let arguments = env::args()
+        .skip(1)
+        .filter_map(|argument| argument.parse::<usize>().ok())
+        .collect::<Vec<usize>>();
+    let population = arguments.first().copied().unwrap_or(64);
+    let workers = arguments
+        .get(1)
+        .copied()
+        .and_then(NonZero::new)
+        .unwrap_or_else(WorkerPool::default_size);
+
+    let config = ExperimentConfig {
+        population_size: population,
+        training_time: 20.0,
+        ..ExperimentConfig::default()
+    };
+    let steps_per_episode = (config.training_time / config.fast_delta_time).ceil() as usize;
+    let shared_resources = SharedResources::new(
+        config,
+        0,
+        None,
+        Some(workers),
+        PopulationFiles::in_directory(&env::temp_dir(), "cannon_ai_bench_missing"),
+    )
+    .expect("Default config is valid");
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        trainer::run_generation(&shared_resources);
+        trainer::evolve_ais(&shared_resources);
+        shared_resources.generation.fetch_add(1, Ordering::SeqCst);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let total_steps = (GENERATIONS * population * steps_per_episode) as f64;
+    println!(
+        "population {population}, {workers} workers: {:.0} steps/s ({total_steps} steps in {elapsed:.2}s)",
+        total_steps / elapsed
+    );
+}
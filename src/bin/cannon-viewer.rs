@@ -0,0 +1,31 @@
+use std::io;
+
+use cannon_ai::{
+    cli::{load_shared_resources, ViewerCli},
+    display,
+    trainer::{self, SimulationOptions},
+};
+use clap::Parser;
+
+fn main() -> Result<(), io::Error> {
+    let args = ViewerCli::parse().watch;
+    let shared_resources =
+        load_shared_resources(args.config, args.seed, args.population, &args.arena)?;
+
+    let simulation = trainer::run_simulation(
+        shared_resources.clone(),
+        SimulationOptions {
+            startup_delay: display::STARTUP_DELAY,
+            max_generations: None,
+            evolve: false,
+            metrics_log: None,
+            checkpoint_every: None,
+            replay_dir: None,
+        },
+    );
+
+    display::run_display(shared_resources.clone());
+
+    simulation.join().expect("Simulation panicked");
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Line and bar charts drawn with raylib primitives, and the state the viewer keeps to feed
+//! them.
+
+use raylib::{
+    color::Color,
+    ffi::{Rectangle, Vector2},
+    prelude::{RaylibDraw, RaylibDrawHandle},
+};
+
+use crate::{environment::WorldSnapshot, fitness::mean};
+
+const TITLE_HEIGHT: f32 = 22.0;
+const AXIS_LABEL_WIDTH: f32 = 44.0;
+const MARGIN: f32 = 8.0;
+
+/// One line of a line chart.
+pub struct Series<'a> {
+    pub label: &'a str,
+    pub color: Color,
+    /// `(x, y)` points in increasing `x`.
+    pub points: &'a [(f32, f32)],
+}
+
+/// Best, mean and worst fitness of every completed generation.
+#[derive(Default)]
+pub struct FitnessTrend {
+    pub best: Vec<(f32, f32)>,
+    pub mean: Vec<(f32, f32)>,
+    pub worst: Vec<(f32, f32)>,
+}
+
+impl FitnessTrend {
+    /// Summarizes the generations of `fitness_history` that are not in the trend yet.
+    pub fn update(&mut self, fitness_history: &[Box<[f32]>]) {
+        for (generation, ai_scores) in fitness_history.iter().enumerate().skip(self.best.len()) {
+            let generation = generation as f32;
+            let best = ai_scores.iter().cloned().fold(f32::MIN, f32::max);
+            let worst = ai_scores.iter().cloned().fold(f32::MAX, f32::min);
+            self.best.push((generation, best));
+            self.mean.push((generation, mean(ai_scores)));
+            self.worst.push((generation, worst));
+        }
+    }
+}
+
+/// Score over time of the episode the selected AI is playing, restarted with every episode.
+#[derive(Default)]
+pub struct RunningScore {
+    ai_index: usize,
+    pub points: Vec<(f32, f32)>,
+}
+
+impl RunningScore {
+    pub fn record(&mut self, world: &WorldSnapshot) {
+        let elapsed_time = world.info.elapsed_time;
+        let last_time = self.points.last().map(|(time, _)| *time);
+        if world.ai_index != self.ai_index || last_time.is_some_and(|time| elapsed_time < time) {
+            self.ai_index = world.ai_index;
+            self.points.clear();
+        }
+        if self
+            .points
+            .last()
+            .is_none_or(|(time, _)| elapsed_time > *time)
+        {
+            self.points.push((elapsed_time, world.info.score));
+        }
+    }
+}
+
+/// Draws every series on shared axes inside `area`, with a legend in the title bar.
+pub fn draw_line_chart(
+    d: &mut RaylibDrawHandle<'_>,
+    area: Rectangle,
+    title: &str,
+    series: &[Series],
+) {
+    let plot = draw_frame(d, area, title);
+    let points = series.iter().flat_map(|series| series.points.iter());
+    let Some((x_range, y_range)) = ranges(points) else {
+        draw_waiting(d, plot);
+        return;
+    };
+    draw_y_labels(d, plot, y_range);
+
+    let mut legend_x = area.x + area.width - MARGIN;
+    for series in series.iter().rev() {
+        legend_x -= d.measure_text(series.label, 10) as f32 + MARGIN;
+        d.draw_text(
+            series.label,
+            legend_x as i32,
+            (area.y + 6.0) as i32,
+            10,
+            series.color,
+        );
+
+        let to_screen = |(x, y): (f32, f32)| Vector2 {
+            x: plot.x + (x - x_range.0) / (x_range.1 - x_range.0) * plot.width,
+            y: plot.y + plot.height - (y - y_range.0) / (y_range.1 - y_range.0) * plot.height,
+        };
+        if let [point] = series.points {
+            d.draw_circle_v(to_screen(*point), 2.0, series.color);
+        }
+        for pair in series.points.windows(2) {
+            d.draw_line_ex(to_screen(pair[0]), to_screen(pair[1]), 2.0, series.color);
+        }
+    }
+}
+/// Draws one bar per value inside `area`, measured from zero. The `highlighted` bar is orange.
+pub fn draw_bar_chart(
+    d: &mut RaylibDrawHandle<'_>,
+    area: Rectangle,
+    title: &str,
+    values: &[f32],
+    highlighted: Option<usize>,
+) {
+    let plot = draw_frame(d, area, title);
+    if values.is_empty() {
+        draw_waiting(d, plot);
+        return;
+    }
+    let y_range = widen((
+        values.iter().cloned().fold(0.0, f32::min),
+        values.iter().cloned().fold(0.0, f32::max),
+    ));
+    draw_y_labels(d, plot, y_range);
+
+    let to_y = |value: f32| {
+        plot.y + plot.height - (value - y_range.0) / (y_range.1 - y_range.0) * plot.height
+    };
+    let zero = to_y(0.0);
+    let bar_width = plot.width / values.len() as f32;
+    for (index, value) in values.iter().enumerate() {
+        let top = to_y(*value).min(zero);
+        let color = if Some(index) == highlighted {
+            Color::ORANGE
+        } else if *value < 0.0 {
+            Color::MAROON
+        } else {
+            Color::BLUE
+        };
+        d.draw_rectangle_rec(
+            Rectangle {
+                x: plot.x + index as f32 * bar_width + bar_width * 0.1,
+                y: top,
+                width: (bar_width * 0.8).max(1.0),
+                height: (to_y(*value) - zero).abs().max(1.0),
+            },
+            color,
+        );
+    }
+    d.draw_line_ex(
+        Vector2 { x: plot.x, y: zero },
+        Vector2 {
+            x: plot.x + plot.width,
+            y: zero,
+        },
+        1.0,
+        Color::DARKGRAY,
+    );
+}
+/// Draws the background, border and title of a chart and returns the area left for plotting.
+fn draw_frame(d: &mut RaylibDrawHandle<'_>, area: Rectangle, title: &str) -> Rectangle {
+    d.draw_rectangle_rec(
+        area,
+        Color {
+            r: 245,
+            g: 245,
+            b: 245,
+            a: 230,
+        },
+    );
+    d.draw_rectangle_lines_ex(area, 1.0, Color::GRAY);
+    d.draw_text(
+        title,
+        (area.x + MARGIN) as i32,
+        (area.y + 4.0) as i32,
+        16,
+        Color::BLACK,
+    );
+    Rectangle {
+        x: area.x + AXIS_LABEL_WIDTH,
+        y: area.y + TITLE_HEIGHT + MARGIN,
+        width: area.width - AXIS_LABEL_WIDTH - MARGIN,
+        height: area.height - TITLE_HEIGHT - 2.0 * MARGIN,
+    }
+}
+fn draw_waiting(d: &mut RaylibDrawHandle<'_>, plot: Rectangle) {
+    d.draw_text(
+        "Waiting for data",
+        plot.x as i32,
+        (plot.y + plot.height / 2.0) as i32,
+        12,
+        Color::GRAY,
+    );
+}
+fn draw_y_labels(d: &mut RaylibDrawHandle<'_>, plot: Rectangle, (low, high): (f32, f32)) {
+    for (value, y) in [(high, plot.y), (low, plot.y + plot.height - 10.0)] {
+        d.draw_text(
+            &format!("{value:.1}"),
+            (plot.x - AXIS_LABEL_WIDTH + 4.0) as i32,
+            y as i32,
+            10,
+            Color::DARKGRAY,
+        );
+    }
+}
+/// The `(low, high)` range of the x and y values, widened so that neither is empty, or `None`
+/// without points.
+fn ranges<'a>(points: impl Iterator<Item = &'a (f32, f32)>) -> Option<((f32, f32), (f32, f32))> {
+    let (x_range, y_range) = points.fold(None, |ranges, (x, y)| {
+        let ((x_low, x_high), (y_low, y_high)) = ranges.unwrap_or(((*x, *x), (*y, *y)));
+        Some((
+            (x_low.min(*x), x_high.max(*x)),
+            (y_low.min(*y), y_high.max(*y)),
+        ))
+    })?;
+    Some((widen(x_range), widen(y_range)))
+}
+/// Makes an empty range one unit wide on each side, so that it can be divided by.
+fn widen((low, high): (f32, f32)) -> (f32, f32) {
+    if high - low <= 0.0 {
+        (low - 1.0, high + 1.0)
+    } else {
+        (low, high)
+    }
+}
@@ -0,0 +1,289 @@
+//! Command line arguments shared by the `cannon-ai` and `cannon-viewer` binaries.
+
+use std::{io, num::NonZero, path::PathBuf};
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use rand::Rng;
+
+use crate::{
+    config::ExperimentConfig,
+    multi_threading::{Checkpoint, PopulationFiles, SharedResources},
+    storage::{MismatchPolicy, NetworkFormat},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "cannon-ai",
+    version,
+    about = "Evolves neural networks that defend a cannon"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Evolve the population and save it when training stops
+    Train(TrainArgs),
+    /// Run a fixed number of episodes and print score statistics
+    Evaluate(EvaluateArgs),
+    /// Write one AI's direction and shooting networks as ONNX models
+    ExportOnnx(ExportOnnxArgs),
+    /// Defend the cannon yourself with the arrow keys and space bar
+    Play(PlayArgs),
+    /// Watch a recorded episode with pause, step, scrub and speed controls
+    Replay(ReplayArgs),
+    /// Play saved populations through the same episodes and compare their best AIs
+    Compare(CompareArgs),
+}
+
+#[derive(Args)]
+pub struct TrainArgs {
+    /// Experiment config file in TOML or JSON format
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Master seed for every random choice, picked at random if omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub population: PopulationArgs,
+    #[command(flatten)]
+    pub arena: ArenaArgs,
+    /// Stop after this many generations instead of running until interrupted
+    #[arg(short, long)]
+    pub generations: Option<usize>,
+    /// Train without opening a window
+    #[arg(long)]
+    pub headless: bool,
+    /// Append per-generation statistics to this .csv or .jsonl file
+    #[arg(long)]
+    pub metrics: Option<PathBuf>,
+    /// Save the population and a checkpoint every this many generations
+    #[arg(long)]
+    pub checkpoint_every: Option<NonZero<usize>>,
+    /// Continue from the checkpoint file, using its config, seed, generation and networks
+    #[arg(long, conflicts_with_all = ["config", "seed"])]
+    pub resume: bool,
+    /// Save a replay of every generation's best episode in this directory
+    #[arg(long)]
+    pub replays: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "cannon-viewer",
+    version,
+    about = "Renders a saved population without evolving it"
+)]
+pub struct ViewerCli {
+    #[command(flatten)]
+    pub watch: WatchArgs,
+}
+
+#[derive(Args)]
+pub struct WatchArgs {
+    /// Experiment config file in TOML or JSON format
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Master seed for every random choice, picked at random if omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub population: PopulationArgs,
+    #[command(flatten)]
+    pub arena: ArenaArgs,
+}
+
+#[derive(Args)]
+pub struct EvaluateArgs {
+    /// Experiment config file in TOML or JSON format
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Master seed for every random choice, picked at random if omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub population: PopulationArgs,
+    #[command(flatten)]
+    pub arena: ArenaArgs,
+    /// Number of episodes every AI plays
+    #[arg(short, long, default_value_t = NonZero::new(10).unwrap())]
+    pub episodes: NonZero<usize>,
+}
+
+#[derive(Args)]
+pub struct ExportOnnxArgs {
+    /// Saved direction networks, in any supported format
+    #[arg(long)]
+    pub direction_path: PathBuf,
+    /// Saved shooting networks, in any supported format
+    #[arg(long)]
+    pub shooting_path: PathBuf,
+    /// Index of the AI to export
+    #[arg(long, default_value_t = 0)]
+    pub ai: usize,
+    /// Directory to write direction.onnx and shooting.onnx to
+    #[arg(short, long, default_value = ".")]
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct PlayArgs {
+    /// Experiment config file in TOML or JSON format
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Seed of the first episode, picked at random if omitted; each replay adds one
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub arena: ArenaArgs,
+    /// Save every step's view rays and keys to this JSON file when the window closes
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Replay file saved by `train --replays`
+    pub path: PathBuf,
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// Direction and shooting network files of one population; repeat for every population
+    #[arg(long, num_args = 2, value_names = ["DIRECTION", "SHOOTING"], action = ArgAction::Append)]
+    pub networks: Vec<PathBuf>,
+    /// Checkpoint file of one population; repeat for every population
+    #[arg(long = "checkpoint", action = ArgAction::Append)]
+    pub checkpoints: Vec<PathBuf>,
+    /// Experiment config file in TOML or JSON format [default: the first checkpoint's config,
+    /// then the default config]
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Seed of the episodes, picked at random if omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub arena: ArenaArgs,
+    /// Number of episodes every contestant plays
+    #[arg(short, long, default_value_t = 20)]
+    pub episodes: usize,
+    /// Number of episodes every AI plays to pick its population's champion, on other seeds than
+    /// the comparison
+    #[arg(long, default_value_t = 5)]
+    pub qualifying_episodes: usize,
+    /// Compare every AI of every population instead of only their champions
+    #[arg(long)]
+    pub all: bool,
+    /// Number of threads that play the episodes [default: one per core]
+    #[arg(short, long)]
+    pub workers: Option<NonZero<usize>>,
+}
+
+#[derive(Args)]
+pub struct PopulationArgs {
+    /// Number of AIs in the population [default: the saved population's size, then the config's]
+    #[arg(short, long)]
+    pub population: Option<NonZero<usize>>,
+    /// Number of threads that play the population [default: one per core]
+    #[arg(short, long)]
+    pub workers: Option<NonZero<usize>>,
+    /// Direction network file to load from and save to [default: direction_ais_<population>.json]
+    #[arg(long)]
+    pub direction_path: Option<PathBuf>,
+    /// Shooting network file to load from and save to [default: shooting_ais_<population>.json]
+    #[arg(long)]
+    pub shooting_path: Option<PathBuf>,
+    /// Checkpoint file holding the config and both networks [default: checkpoint_<population>.json]
+    #[arg(long)]
+    pub checkpoint_path: Option<PathBuf>,
+    /// What to do when saved networks do not fit the population size or view rays
+    #[arg(long, value_enum, default_value_t = MismatchPolicy::Error)]
+    pub on_mismatch: MismatchPolicy,
+    /// Format the network files are saved in, which also sets their default extension; loading
+    /// detects the format
+    #[arg(long, value_enum, default_value_t = NetworkFormat::Json)]
+    pub format: NetworkFormat,
+}
+
+#[derive(Args)]
+pub struct ArenaArgs {
+    /// Arena width, which is also the initial window width
+    #[arg(long, default_value_t = 1000.0)]
+    pub width: f32,
+    /// Arena height, which is also the initial window height
+    #[arg(long, default_value_t = 750.0)]
+    pub height: f32,
+}
+
+/// Continues the population saved in the checkpoint file, named after `--population` or the
+/// default config's population size unless `--checkpoint-path` is given.
+pub fn resume_shared_resources(
+    population: PopulationArgs,
+    arena: &ArenaArgs,
+) -> Result<SharedResources, io::Error> {
+    let checkpoint_path = population.checkpoint_path.clone().unwrap_or_else(|| {
+        let total_ais = population
+            .population
+            .map_or(ExperimentConfig::default().population_size, usize::from);
+        PathBuf::from(format!("checkpoint_{total_ais}.json"))
+    });
+    let checkpoint = Checkpoint::load(&checkpoint_path)?;
+    println!(
+        "Resuming {} at generation {} with seed {}",
+        checkpoint_path.display(),
+        checkpoint.generation,
+        checkpoint.seed
+    );
+    let shared_resources = SharedResources::from_checkpoint(
+        checkpoint,
+        population.population,
+        population.workers,
+        PopulationFiles {
+            direction_path: population.direction_path,
+            shooting_path: population.shooting_path,
+            checkpoint_path: Some(checkpoint_path),
+            on_mismatch: population.on_mismatch,
+            format: population.format,
+        },
+    )?;
+    shared_resources.set_dimensions(arena.width, arena.height);
+    Ok(shared_resources)
+}
+/// Returns `seed`, or a random one if none was given, and prints it so that a run can be
+/// repeated.
+pub fn resolve_seed(seed: Option<u64>) -> u64 {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    println!("Using seed {seed}");
+    seed
+}
+/// Builds the population described by the common arguments, printing the seed in use so that a
+/// run can be repeated.
+pub fn load_shared_resources(
+    config_path: Option<PathBuf>,
+    seed: Option<u64>,
+    population: PopulationArgs,
+    arena: &ArenaArgs,
+) -> Result<SharedResources, io::Error> {
+    let config = match config_path {
+        Some(config_path) => ExperimentConfig::load(&config_path)?,
+        None => ExperimentConfig::default(),
+    };
+    let seed = resolve_seed(seed);
+    let shared_resources = SharedResources::new(
+        config,
+        seed,
+        population.population,
+        population.workers,
+        PopulationFiles {
+            direction_path: population.direction_path,
+            shooting_path: population.shooting_path,
+            checkpoint_path: population.checkpoint_path,
+            on_mismatch: population.on_mismatch,
+            format: population.format,
+        },
+    )?;
+    shared_resources.set_dimensions(arena.width, arena.height);
+    Ok(shared_resources)
+}
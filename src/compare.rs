@@ -0,0 +1,313 @@
+//! Head-to-head evaluation of saved populations outside training. Every contestant plays the
+//! same seeded episodes, so differences between their results come from the networks rather
+//! than from the enemies they happened to face.
+
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    config::ExperimentConfig,
+    entity::Point,
+    environment::{CannonEnv, NetworkPolicy, StepInfo},
+    fitness::{mean, standard_deviation},
+    multi_threading::Checkpoint,
+    neural_network::NeuralNetwork,
+    seeding::{stream_seed, RngStream},
+    storage::{self, LoadError},
+    worker_pool::{Job, WorkerPool},
+};
+
+/// Two-sided 95% critical values of Student's t distribution for 1 to 30 degrees of freedom;
+/// larger samples use the normal distribution's 1.96.
+const T_CRITICAL_95: [f32; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+/// [`RngStream::Comparison`] generation of the episodes that pick each population's champion.
+const QUALIFYING_GENERATION: usize = 0;
+/// [`RngStream::Comparison`] generation of the episodes every contestant is compared on.
+const COMPARISON_GENERATION: usize = 1;
+
+/// A saved population taking part in a comparison.
+pub struct Population {
+    /// The file it was loaded from, used to label its contestants.
+    pub name: String,
+    pub direction_ais: Box<[NeuralNetwork]>,
+    pub shooting_ais: Box<[NeuralNetwork]>,
+}
+
+impl Population {
+    /// Loads a pair of saved network files in any supported format.
+    pub fn from_files(direction_path: &Path, shooting_path: &Path) -> Result<Self, io::Error> {
+        let load = |path: &Path| {
+            storage::load_networks(path)?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} does not exist", path.display()),
+                )
+            })
+        };
+        let population = Self {
+            name: direction_path.display().to_string(),
+            direction_ais: load(direction_path)?,
+            shooting_ais: load(shooting_path)?,
+        };
+        if population.direction_ais.len() != population.shooting_ais.len() {
+            return Err(LoadError::PopulationSize {
+                path: shooting_path.to_path_buf(),
+                expected: population.direction_ais.len(),
+                found: population.shooting_ais.len(),
+            }
+            .into());
+        }
+        Ok(population)
+    }
+    /// Loads the population of a checkpoint, along with the config it was trained under.
+    pub fn from_checkpoint(path: &Path) -> Result<(Self, ExperimentConfig), io::Error> {
+        let checkpoint = Checkpoint::load(path)?;
+        let population = Self {
+            name: path.display().to_string(),
+            direction_ais: checkpoint.direction_ais,
+            shooting_ais: checkpoint.shooting_ais,
+        };
+        Ok((population, checkpoint.config))
+    }
+    /// Checks that every network can be driven by `config`'s view rays.
+    pub fn check(&self, config: &ExperimentConfig) -> Result<(), LoadError> {
+        let path = Path::new(&self.name);
+        storage::check_networks(
+            path,
+            &self.direction_ais,
+            config.total_view_rays,
+            NetworkPolicy::DIRECTION_OUTPUTS,
+        )?;
+        storage::check_networks(
+            path,
+            &self.shooting_ais,
+            config.total_view_rays,
+            NetworkPolicy::SHOOTING_OUTPUTS,
+        )
+    }
+}
+
+/// How a comparison is run.
+pub struct CompareOptions {
+    /// Number of episodes every contestant plays.
+    pub episodes: usize,
+    /// Number of episodes every AI plays to pick its population's champion.
+    pub qualifying_episodes: usize,
+    /// Compare every AI instead of only each population's champion.
+    pub every_ai: bool,
+}
+
+/// One AI's results over the comparison episodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contestant {
+    pub name: String,
+    /// Index of its population in the list given to [`compare`].
+    pub population: usize,
+    pub ai_index: usize,
+    pub episodes: Vec<StepInfo>,
+}
+
+impl Contestant {
+    pub fn scores(&self) -> Vec<f32> {
+        self.episodes.iter().map(|info| info.score).collect()
+    }
+    pub fn mean_score(&self) -> f32 {
+        mean(&self.scores())
+    }
+    /// Half-width of the 95% confidence interval of the mean score.
+    pub fn confidence_interval(&self) -> f32 {
+        let scores = self.scores();
+        let t_critical = T_CRITICAL_95
+            .get(scores.len().saturating_sub(2))
+            .copied()
+            .unwrap_or(1.96);
+        t_critical * standard_deviation(&scores) / (scores.len() as f32).sqrt()
+    }
+    /// Fraction of the spawned enemies that were shot.
+    pub fn kill_rate(&self) -> f32 {
+        let totals = self.totals();
+        ratio(totals.enemies_killed, totals.enemies_spawned)
+    }
+    /// Fraction of the fired bullets that killed an enemy.
+    pub fn accuracy(&self) -> f32 {
+        let totals = self.totals();
+        ratio(totals.enemies_killed, totals.bullets_fired)
+    }
+    fn totals(&self) -> StepInfo {
+        let mut totals = StepInfo::default();
+        for info in self.episodes.iter() {
+            totals += info;
+        }
+        totals
+    }
+}
+
+/// Seeds of the `count` episodes of one comparison stage.
+pub fn episode_seeds(seed: u64, generation: usize, count: usize) -> Vec<u64> {
+    (0..count)
+        .map(|episode| stream_seed(seed, RngStream::Comparison, generation, episode))
+        .collect()
+}
+/// Plays every AI of `populations`, or only the one that scores best over the qualifying
+/// episodes, through the same comparison episodes. The contestants are returned best first.
+pub fn compare(
+    config: &Arc<ExperimentConfig>,
+    dimensions: &Point,
+    seed: u64,
+    populations: &[Population],
+    options: &CompareOptions,
+    worker_pool: &WorkerPool,
+) -> Result<Vec<Contestant>, String> {
+    if populations.len() < 2 {
+        return Err("A comparison needs at least two populations".to_string());
+    }
+    if options.episodes < 2 {
+        return Err(
+            "A comparison needs at least two episodes for confidence intervals".to_string(),
+        );
+    }
+    for population in populations {
+        population
+            .check(config)
+            .map_err(|error| error.to_string())?;
+    }
+
+    let mut entrants = vec![];
+    for (population_index, population) in populations.iter().enumerate() {
+        let total_ais = population.direction_ais.len();
+        if options.every_ai {
+            entrants.extend((0..total_ais).map(|ai_index| (population_index, ai_index)));
+        } else {
+            let champion =
+                pick_champion(config, dimensions, seed, population, options, worker_pool);
+            entrants.push((population_index, champion));
+        }
+    }
+
+    let networks = entrants
+        .iter()
+        .map(|&(population, ai_index)| {
+            (
+                populations[population].direction_ais[ai_index].clone(),
+                populations[population].shooting_ais[ai_index].clone(),
+            )
+        })
+        .collect();
+    let seeds = episode_seeds(seed, COMPARISON_GENERATION, options.episodes);
+    let results = play_all(config, dimensions, networks, &seeds, worker_pool);
+
+    let mut contestants = entrants
+        .into_iter()
+        .zip(results)
+        .map(|((population, ai_index), episodes)| Contestant {
+            name: format!("{} AI {ai_index}", populations[population].name),
+            population,
+            ai_index,
+            episodes,
+        })
+        .collect::<Vec<Contestant>>();
+    contestants.sort_by(|a, b| b.mean_score().total_cmp(&a.mean_score()));
+    Ok(contestants)
+}
+/// Formats `contestants` as a table, one row per contestant in the given order.
+pub fn comparison_table(contestants: &[Contestant]) -> String {
+    let name_width = contestants
+        .iter()
+        .map(|contestant| contestant.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Contestant".len());
+    let mut table = format!(
+        "{:>4}  {:<name_width$} {:>10} {:>10} {:>10} {:>10}\n",
+        "Rank", "Contestant", "Mean", "95% CI", "Kill rate", "Accuracy"
+    );
+    for (rank, contestant) in contestants.iter().enumerate() {
+        table += &format!(
+            "{:>4}  {:<name_width$} {:>10.2} {:>10} {:>9.1}% {:>9.1}%\n",
+            rank + 1,
+            contestant.name,
+            contestant.mean_score(),
+            format!("±{:.2}", contestant.confidence_interval()),
+            contestant.kill_rate() * 100.0,
+            contestant.accuracy() * 100.0,
+        );
+    }
+    table
+}
+/// The AI of `population` with the highest mean score over the qualifying episodes, which use
+/// different seeds than the comparison so that a champion is not picked on its test episodes.
+fn pick_champion(
+    config: &Arc<ExperimentConfig>,
+    dimensions: &Point,
+    seed: u64,
+    population: &Population,
+    options: &CompareOptions,
+    worker_pool: &WorkerPool,
+) -> usize {
+    if population.direction_ais.len() == 1 || options.qualifying_episodes == 0 {
+        return 0;
+    }
+    let networks = population
+        .direction_ais
+        .iter()
+        .cloned()
+        .zip(population.shooting_ais.iter().cloned())
+        .collect();
+    let seeds = episode_seeds(seed, QUALIFYING_GENERATION, options.qualifying_episodes);
+    play_all(config, dimensions, networks, &seeds, worker_pool)
+        .iter()
+        .map(|episodes| mean(&episodes.iter().map(|info| info.score).collect::<Vec<f32>>()))
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(ai_index, _)| ai_index)
+}
+/// Plays every `(direction, shooting)` pair through one episode per seed on `worker_pool` and
+/// returns each pair's results in order.
+fn play_all(
+    config: &Arc<ExperimentConfig>,
+    dimensions: &Point,
+    networks: Vec<(NeuralNetwork, NeuralNetwork)>,
+    seeds: &[u64],
+    worker_pool: &WorkerPool,
+) -> Vec<Vec<StepInfo>> {
+    let results = Arc::new(Mutex::new(vec![vec![]; networks.len()]));
+    let seeds = Arc::new(seeds.to_vec());
+    let jobs = networks
+        .into_iter()
+        .enumerate()
+        .map(|(index, (direction_ai, shooting_ai))| {
+            let config = Arc::clone(config);
+            let dimensions = dimensions.clone();
+            let seeds = Arc::clone(&seeds);
+            let results = Arc::clone(&results);
+            Box::new(move || {
+                let mut environment = CannonEnv::new(config, dimensions);
+                let mut policy = NetworkPolicy {
+                    direction_ai: &direction_ai,
+                    shooting_ai: &shooting_ai,
+                };
+                let episodes = seeds
+                    .iter()
+                    .map(|seed| environment.run_episode(*seed, &mut policy))
+                    .collect();
+                lock_with_error!(results)[index] = episodes;
+            }) as Job
+        })
+        .collect();
+    worker_pool.run_all(jobs);
+    let results = lock_with_error!(results);
+    results.clone()
+}
+fn ratio(numerator: usize, denominator: usize) -> f32 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f32 / denominator as f32
+}
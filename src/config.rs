@@ -0,0 +1,169 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entity::EntitySizes, fitness::FitnessAggregate, initializer::Initializer,
+    neural_network::Crossover, selection::Selection,
+};
+
+/// Every simulation and training constant of an experiment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExperimentConfig {
+    /// Number of AIs, unless a saved population or the command line says otherwise.
+    pub population_size: usize,
+    pub gun_rotate_velocity: f32,
+    pub bullet_speed: f32,
+    pub bullet_cooldown: f32,
+    pub enemy_cooldown: f32,
+    pub enemy_speed: f32,
+    pub enemy_spawn_distance: f32,
+    pub total_view_rays: usize,
+    pub view_ray_length: f32,
+    pub fast_delta_time: f32,
+    pub training_time: f32,
+    pub episodes_per_genome: usize,
+    /// Give every AI the same spawn sequences so that fitness compares like with like.
+    pub shared_episode_seeds: bool,
+    pub fitness_aggregate: FitnessAggregate,
+    pub max_tweak_change: f32,
+    pub initializer: Initializer,
+    /// Combine two surviving parents into each child; `None` clones a single parent.
+    pub crossover: Option<Crossover>,
+    pub selection: Selection,
+    pub entity_sizes: EntitySizes,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            population_size: 10,
+            gun_rotate_velocity: 0.75,
+            bullet_speed: 150.0,
+            bullet_cooldown: 1.0,
+            enemy_cooldown: 3.0,
+            enemy_speed: 45.0,
+            enemy_spawn_distance: 1.0,
+            total_view_rays: 20,
+            view_ray_length: 400.0,
+            fast_delta_time: 0.005,
+            training_time: 60.0,
+            episodes_per_genome: 1,
+            shared_episode_seeds: true,
+            fitness_aggregate: FitnessAggregate::default(),
+            max_tweak_change: 0.05,
+            initializer: Initializer::default(),
+            crossover: None,
+            selection: Selection::default(),
+            entity_sizes: EntitySizes::default(),
+        }
+    }
+}
+
+impl ExperimentConfig {
+    /// Reads a config from a `.json` file, or from TOML for any other extension.
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        let contents = fs::read_to_string(path)?;
+        let config: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&contents).map_err(|error| invalid_config(path, error))?
+        } else {
+            toml::from_str(&contents).map_err(|error| invalid_config(path, error))?
+        };
+        config
+            .validate()
+            .map_err(|error| invalid_config(path, error))?;
+        Ok(config)
+    }
+    /// FNV-1a hash of the config's JSON form, stable across runs and builds, to tell apart
+    /// results from different configs.
+    pub fn hash(&self) -> u64 {
+        let json = serde_json::to_string(self).expect("Configs always serialize");
+        json.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        if self.population_size == 0 {
+            return Err("population_size must be at least 1".to_string());
+        }
+        if self.total_view_rays < 2 {
+            return Err("total_view_rays must be at least 2".to_string());
+        }
+        let positive_fields = [
+            ("gun_rotate_velocity", self.gun_rotate_velocity),
+            ("bullet_speed", self.bullet_speed),
+            ("bullet_cooldown", self.bullet_cooldown),
+            ("enemy_cooldown", self.enemy_cooldown),
+            ("enemy_speed", self.enemy_speed),
+            ("view_ray_length", self.view_ray_length),
+            ("fast_delta_time", self.fast_delta_time),
+            ("training_time", self.training_time),
+            ("max_tweak_change", self.max_tweak_change),
+            (
+                "entity_sizes.cannon_radius",
+                self.entity_sizes.cannon_radius,
+            ),
+            (
+                "entity_sizes.barrel_height",
+                self.entity_sizes.barrel_height,
+            ),
+            ("entity_sizes.enemy_width", self.entity_sizes.enemy_width),
+            ("entity_sizes.enemy_height", self.entity_sizes.enemy_height),
+            ("entity_sizes.bullet_width", self.entity_sizes.bullet_width),
+            (
+                "entity_sizes.bullet_height",
+                self.entity_sizes.bullet_height,
+            ),
+        ];
+        for (name, value) in positive_fields {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(format!("{name} must be positive, got {value}"));
+            }
+        }
+        if self.episodes_per_genome == 0 {
+            return Err("episodes_per_genome must be at least 1".to_string());
+        }
+        self.fitness_aggregate
+            .validate()
+            .map_err(|error| format!("fitness_aggregate: {error}"))?;
+        self.initializer
+            .validate()
+            .map_err(|error| format!("initializer: {error}"))?;
+        self.selection
+            .validate()
+            .map_err(|error| format!("selection: {error}"))?;
+        // Keeping every AI as an elite would leave none to replace, so evolution would stop.
+        if self.selection.elite_count() >= self.population_size {
+            return Err(format!(
+                "selection: Elitism count must be less than population_size {}, got {}",
+                self.population_size,
+                self.selection.elite_count()
+            ));
+        }
+        if self.crossover == Some(Crossover::MultiPoint { points: 0 }) {
+            return Err("crossover.points must be at least 1".to_string());
+        }
+        if !(self.enemy_spawn_distance.is_finite() && self.enemy_spawn_distance >= 0.0) {
+            return Err(format!(
+                "enemy_spawn_distance must not be negative, got {}",
+                self.enemy_spawn_distance
+            ));
+        }
+        if self.view_ray_length <= self.entity_sizes.cannon_radius {
+            return Err(
+                "view_ray_length must be longer than entity_sizes.cannon_radius".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+fn invalid_config(path: &Path, error: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid config {}: {}", path.display(), error.to_string()),
+    )
+}
@@ -0,0 +1,145 @@
+//! Episodes played by a person, saved so that they can be studied or used as supervised
+//! training data for the networks.
+
+use std::{fs, io, path::Path};
+
+use na::DVector;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ExperimentConfig,
+    environment::{Action, NetworkPolicy, Observation, Rotation},
+    storage,
+};
+
+/// What the player saw before one step and what they chose.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DemonstrationStep {
+    pub rays: Box<[f32]>,
+    pub can_shoot: bool,
+    /// Index into [`Rotation::ALL`], like the direction network's outputs.
+    pub rotation: usize,
+    /// Whether a bullet was fired, which needs `can_shoot`.
+    pub shoot: bool,
+    pub reward: f32,
+}
+
+/// One recorded episode.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DemonstrationEpisode {
+    pub seed: u64,
+    pub score: f32,
+    pub enemies_killed: usize,
+    pub steps: Vec<DemonstrationStep>,
+}
+
+/// Every episode of a play session, with the config they were played under.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Demonstration {
+    pub config: ExperimentConfig,
+    pub episodes: Vec<DemonstrationEpisode>,
+}
+
+impl Demonstration {
+    pub fn new(config: ExperimentConfig) -> Self {
+        Self {
+            config,
+            episodes: vec![],
+        }
+    }
+    /// Starts recording a new episode.
+    pub fn start_episode(&mut self, seed: u64) {
+        self.episodes.push(DemonstrationEpisode {
+            seed,
+            score: 0.0,
+            enemies_killed: 0,
+            steps: vec![],
+        });
+    }
+    /// Appends a step to the current episode, starting one with seed 0 if there is none.
+    pub fn record(&mut self, observation: &Observation, action: Action, reward: f32) {
+        if self.episodes.is_empty() {
+            self.start_episode(0);
+        }
+        let episode = self.episodes.last_mut().unwrap();
+        episode.score += reward;
+        episode.steps.push(DemonstrationStep {
+            rays: observation.rays.clone(),
+            can_shoot: observation.can_shoot,
+            rotation: action.rotation.index(),
+            shoot: action.shoot && observation.can_shoot,
+            reward,
+        });
+    }
+    /// Sets the kill count of the current episode once it is over.
+    pub fn finish_episode(&mut self, enemies_killed: usize) {
+        if let Some(episode) = self.episodes.last_mut() {
+            episode.enemies_killed = enemies_killed;
+        }
+    }
+    pub fn total_steps(&self) -> usize {
+        self.episodes
+            .iter()
+            .map(|episode| episode.steps.len())
+            .sum()
+    }
+    /// `(rays, one-hot rotation)` pairs for training a direction network.
+    pub fn direction_samples(&self) -> Vec<(DVector<f32>, DVector<f32>)> {
+        self.steps()
+            .map(|step| {
+                (
+                    DVector::from_column_slice(&step.rays),
+                    one_hot(NetworkPolicy::DIRECTION_OUTPUTS, step.rotation),
+                )
+            })
+            .collect()
+    }
+    /// `(rays, one-hot shoot or hold)` pairs for training a shooting network, from the steps
+    /// where the player was able to shoot.
+    pub fn shooting_samples(&self) -> Vec<(DVector<f32>, DVector<f32>)> {
+        self.steps()
+            .filter(|step| step.can_shoot)
+            .map(|step| {
+                (
+                    DVector::from_column_slice(&step.rays),
+                    one_hot(NetworkPolicy::SHOOTING_OUTPUTS, usize::from(!step.shoot)),
+                )
+            })
+            .collect()
+    }
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        storage::write_atomically(path, serde_json::to_string(self)?.as_bytes())
+    }
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid demonstration {}: {error}", path.display()),
+            )
+        })
+    }
+    fn steps(&self) -> impl Iterator<Item = &DemonstrationStep> {
+        self.episodes
+            .iter()
+            .flat_map(|episode| episode.steps.iter())
+    }
+}
+
+/// The action for the arrow keys and space bar that are held down.
+pub fn keyboard_action(left: bool, right: bool, space: bool) -> Action {
+    let rotation = match (left, right) {
+        (true, false) => Rotation::CounterClockwise,
+        (false, true) => Rotation::Clockwise,
+        _ => Rotation::Hold,
+    };
+    Action {
+        rotation,
+        shoot: space,
+    }
+}
+fn one_hot(size: usize, index: usize) -> DVector<f32> {
+    let mut values = DVector::zeros(size);
+    values[index] = 1.0;
+    values
+}
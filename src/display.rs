@@ -0,0 +1,469 @@
+macro_rules! regular_button {
+    ($text:expr, $position:expr, $on_click_up:expr) => {
+        Rc::new(RefCell::new(Button::build(
+            $text.to_string(),
+            &$position,
+            Color::BLACK,
+            None,
+            Some(Box::new(|button: &mut Button| {
+                button.font_color = Color {
+                    r: 80,
+                    g: 80,
+                    b: 80,
+                    a: 255,
+                };
+            })),
+            Some(Box::new(|button: &mut Button| {
+                button.font_color = Color {
+                    r: 50,
+                    g: 50,
+                    b: 50,
+                    a: 255,
+                };
+            })),
+            Some(Box::new(|button: &mut Button| {
+                button.font_color = Color::BLACK;
+            })),
+            Some($on_click_up),
+        )))
+    };
+}
+
+use std::{
+    cell::{Cell, RefCell},
+    num::NonZero,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{self, Duration},
+};
+
+use raylib::{color::Color, ffi::Rectangle, prelude::RaylibDraw, RaylibHandle};
+
+use crate::{
+    charts::{self, FitnessTrend, RunningScore, Series},
+    config::ExperimentConfig,
+    entity::{Point, Sprite},
+    environment::{self, WorldSnapshot},
+    multi_threading::SharedResources,
+    network_view::{self, DIRECTION_LABELS, SHOOTING_LABELS},
+    ui::Button,
+    world_view,
+};
+
+pub const STARTUP_DELAY: Duration = Duration::from_secs(6);
+const NETWORK_PANEL_WIDTH: f32 = 380.0;
+const CHART_WIDTH: f32 = 300.0;
+const CHART_HEIGHT: f32 = 170.0;
+
+/// Debug panels the user can switch on from the buttons.
+#[derive(Default)]
+struct Overlays {
+    networks: Cell<bool>,
+    view_rays: Cell<bool>,
+    charts: Cell<bool>,
+}
+
+pub fn run_display(shared_resources: SharedResources) {
+    let (mut rl, thread) = start_raylib(&shared_resources.dimensions);
+    {
+        let now = std::time::Instant::now();
+        let total_wait = time::Duration::from_secs(5);
+        while now.elapsed() < total_wait && !rl.window_should_close() {
+            if rl.is_window_resized() {
+                update_dimensions(&rl, &shared_resources);
+            }
+
+            let mut d: raylib::prelude::RaylibDrawHandle<'_> = rl.begin_drawing(&thread);
+            d.clear_background(Color::RAYWHITE);
+            d.draw_text(
+                "You have 5 seconds to change\nyour window size",
+                50,
+                200,
+                48,
+                Color::RED,
+            );
+            thread::sleep(time::Duration::from_millis(100));
+        }
+    }
+
+    let overlays = Rc::new(Overlays::default());
+    let mut buttons = create_buttons(
+        &shared_resources.total_ais,
+        &shared_resources.is_real_time,
+        &shared_resources.selected_ai,
+        &overlays,
+    );
+    let mut fitness_trend = FitnessTrend::default();
+    let mut running_score = RunningScore::default();
+
+    while !rl.window_should_close() && shared_resources.is_running.load(Ordering::SeqCst) {
+        if rl.is_window_resized() {
+            update_dimensions(&rl, &shared_resources);
+        }
+
+        let world = selected_world(&shared_resources);
+        if let Some(world) = world.as_ref() {
+            running_score.record(world);
+        }
+        let mut d = rl.begin_drawing(&thread);
+        update_display(
+            &mut d,
+            &mut buttons,
+            world.as_ref(),
+            &shared_resources.config,
+            &overlays,
+        );
+        if let (true, Some(world)) = (overlays.networks.get(), world.as_ref()) {
+            draw_network_panel(&mut d, &shared_resources, world);
+        }
+        if overlays.charts.get() {
+            fitness_trend.update(&lock_with_error!(shared_resources.fitness_history));
+            draw_chart_panel(&mut d, &shared_resources, &fitness_trend, &running_score);
+        }
+        for button in buttons.iter_mut() {
+            button.borrow_mut().update(&d);
+        }
+        display_info(
+            shared_resources.config.training_time,
+            &shared_resources.dimensions,
+            world.as_ref(),
+            d,
+        );
+    }
+
+    drop(rl);
+    shared_resources.is_running.store(false, Ordering::SeqCst);
+}
+/// The latest snapshot of the selected AI's world, or `None` until its worker has published one.
+fn selected_world(shared_resources: &SharedResources) -> Option<WorldSnapshot> {
+    let selected_ai = { *lock_with_error!(shared_resources.selected_ai) };
+    lock_with_error!(shared_resources.selected_world)
+        .as_ref()
+        .filter(|world| world.ai_index == selected_ai)
+        .cloned()
+}
+fn display_info(
+    training_time: f32,
+    dimensions: &Arc<Mutex<Point>>,
+    world: Option<&WorldSnapshot>,
+    mut d: raylib::prelude::RaylibDrawHandle<'_>,
+) {
+    let elapsed_simulation_time = world.map_or(0, |world| world.info.elapsed_time as i32);
+    let center_x = { lock_with_error!(dimensions).x / 2.0 };
+    d.draw_text(
+        format!("Elapsed time: {elapsed_simulation_time}/{training_time}s").as_str(),
+        (center_x - 200.0) as i32,
+        50,
+        40,
+        Color::BLACK,
+    );
+}
+fn create_buttons(
+    total_ais_clone: &Arc<NonZero<usize>>,
+    is_real_time: &Arc<AtomicBool>,
+    selected_ai_clone: &Arc<Mutex<usize>>,
+    overlays: &Rc<Overlays>,
+) -> Box<[Rc<RefCell<Button>>]> {
+    let selected_ai = {
+        let lock = lock_with_error!(selected_ai_clone);
+        *lock
+    };
+    #[allow(unused_assignments)]
+    let mut decrement_selected_ai_button: Option<Rc<RefCell<Button>>> = None;
+    let mut increment_selected_ai_button: Option<Rc<RefCell<Button>>> = None;
+    decrement_selected_ai_button = Some(regular_button!(
+        if selected_ai == 0 { " " } else { "<" },
+        Point { x: 5.0, y: 5.0 },
+        {
+            let selected_ai_clone = Arc::clone(selected_ai_clone);
+            let increment_selected_ai_button = increment_selected_ai_button.clone();
+            Box::new(move |self_: &mut Button| {
+                let mut selected_ai = lock_with_error!(selected_ai_clone);
+                if *selected_ai > 0 {
+                    *selected_ai -= 1;
+                }
+                if *selected_ai == 0 {
+                    self_.text = " ".to_string();
+                }
+                if let Some(ref button) = increment_selected_ai_button {
+                    button.borrow_mut().text = ">".to_string();
+                }
+            })
+        }
+    ));
+    increment_selected_ai_button = Some(regular_button!(
+        {
+            if selected_ai == Into::<usize>::into(**total_ais_clone) - 1 {
+                " "
+            } else {
+                ">"
+            }
+        },
+        Point { x: 25.0, y: 5.0 },
+        {
+            let total_ais_clone = Arc::clone(total_ais_clone);
+            let selected_ai_clone = Arc::clone(selected_ai_clone);
+            let decrement_selected_ai_button = decrement_selected_ai_button.clone();
+            Box::new(move |self_: &mut Button| {
+                let mut selected_ai = lock_with_error!(selected_ai_clone);
+                *selected_ai += 1;
+                if *selected_ai >= Into::<usize>::into(*total_ais_clone) - 1 {
+                    *selected_ai = Into::<usize>::into(*total_ais_clone) - 1;
+                    self_.text = " ".to_string();
+                }
+                if let Some(ref button) = decrement_selected_ai_button {
+                    button.borrow_mut().text = "<".to_string();
+                }
+            })
+        }
+    ));
+    vec![
+        decrement_selected_ai_button.unwrap(),
+        increment_selected_ai_button.unwrap(),
+        regular_button!("Speed Up", Point { x: 5.0, y: 30.0 }, {
+            let is_real_time = Arc::clone(is_real_time);
+            Box::new(move |self_: &mut Button| {
+                let current_state = is_real_time.load(Ordering::SeqCst);
+                is_real_time.store(!current_state, Ordering::SeqCst);
+
+                if is_real_time.load(Ordering::SeqCst) {
+                    self_.text = "Speed Up".to_string();
+                } else {
+                    self_.text = "Slow Down".to_string();
+                }
+            })
+        }),
+        regular_button!("Show Networks", Point { x: 5.0, y: 55.0 }, {
+            let overlays = Rc::clone(overlays);
+            Box::new(move |self_: &mut Button| {
+                let show = !overlays.networks.get();
+                overlays.networks.set(show);
+                self_.text = if show {
+                    "Hide Networks"
+                } else {
+                    "Show Networks"
+                }
+                .to_string();
+            })
+        }),
+        regular_button!("Show Rays", Point { x: 5.0, y: 80.0 }, {
+            let overlays = Rc::clone(overlays);
+            Box::new(move |self_: &mut Button| {
+                let show = !overlays.view_rays.get();
+                overlays.view_rays.set(show);
+                self_.text = if show { "Hide Rays" } else { "Show Rays" }.to_string();
+            })
+        }),
+        regular_button!("Show Charts", Point { x: 5.0, y: 105.0 }, {
+            let overlays = Rc::clone(overlays);
+            Box::new(move |self_: &mut Button| {
+                let show = !overlays.charts.get();
+                overlays.charts.set(show);
+                self_.text = if show { "Hide Charts" } else { "Show Charts" }.to_string();
+            })
+        }),
+    ]
+    .into_boxed_slice()
+}
+fn start_raylib(dimensions: &Arc<Mutex<Point>>) -> (RaylibHandle, raylib::RaylibThread) {
+    let (width, height) = {
+        let dimensions = lock_with_error!(dimensions);
+        (dimensions.x as i32, dimensions.y as i32)
+    };
+    let (mut rl, thread) = raylib::init()
+        .size(width, height)
+        .title("AI Cannon")
+        .resizable()
+        //.fullscreen()
+        .build();
+
+    rl.set_target_fps(60);
+    (rl, thread)
+}
+fn update_display(
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+    buttons: &mut Box<[Rc<RefCell<Button>>]>,
+    world: Option<&WorldSnapshot>,
+    config: &ExperimentConfig,
+    overlays: &Overlays,
+) {
+    d.clear_background(Color::RAYWHITE);
+
+    draw_buttons(buttons, d);
+    if let Some(world) = world {
+        draw_entities(world, config, overlays, d);
+    }
+}
+fn draw_buttons(
+    buttons: &mut Box<[Rc<RefCell<Button>>]>,
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+) {
+    for button in buttons.iter_mut() {
+        button.borrow_mut().draw(d);
+    }
+}
+fn draw_entities(
+    world: &WorldSnapshot,
+    config: &ExperimentConfig,
+    overlays: &Overlays,
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+) {
+    if overlays.view_rays.get() {
+        draw_view_rays(world, config, d);
+    }
+    world_view::draw_world(d, &world.cannon, &world.bullets, &world.enemies);
+}
+/// Draws every view ray of the cannon and the circle it can see within. Rays that see an enemy
+/// stop at the distance they report and are labeled with their normalized reading.
+fn draw_view_rays(
+    world: &WorldSnapshot,
+    config: &ExperimentConfig,
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+) {
+    let center = &world.cannon.position;
+    d.draw_circle_lines(
+        center.x as i32,
+        center.y as i32,
+        config.view_ray_length,
+        Color::GRAY,
+    );
+    let total_rays = world.observation.rays.len();
+    for (ray, reading) in world.observation.rays.iter().enumerate() {
+        let angle = environment::view_ray_angle(world.cannon.direction, ray, total_rays);
+        let sees_enemy = *reading > 0.0;
+        let length = if sees_enemy {
+            reading * config.view_ray_length + config.entity_sizes.cannon_radius
+        } else {
+            config.view_ray_length
+        };
+        let end = Point {
+            x: center.x + angle.cos() * length,
+            y: center.y + angle.sin() * length,
+        };
+        if !sees_enemy {
+            d.draw_line(
+                center.x as i32,
+                center.y as i32,
+                end.x as i32,
+                end.y as i32,
+                Color::LIGHTGRAY,
+            );
+            continue;
+        }
+        d.draw_line(
+            center.x as i32,
+            center.y as i32,
+            end.x as i32,
+            end.y as i32,
+            Color::RED,
+        );
+        d.draw_circle(end.x as i32, end.y as i32, 4.0, Color::RED);
+        d.draw_text(
+            &format!("{reading:.2}"),
+            (end.x + 6.0) as i32,
+            (end.y - 6.0) as i32,
+            12,
+            Color::MAROON,
+        );
+    }
+}
+/// Draws the selected AI's direction and shooting networks down the right edge of the window,
+/// fed with the rays of `world`.
+fn draw_network_panel(
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+    shared_resources: &SharedResources,
+    world: &WorldSnapshot,
+) {
+    let direction_ai = lock_with_error!(shared_resources.direction_ais)
+        .get(world.ai_index)
+        .cloned();
+    let shooting_ai = lock_with_error!(shared_resources.shooting_ais)
+        .get(world.ai_index)
+        .cloned();
+    let (Some(direction_ai), Some(shooting_ai)) = (direction_ai, shooting_ai) else {
+        return;
+    };
+    let dimensions = { lock_with_error!(shared_resources.dimensions).clone() };
+    let top = 90.0;
+    let height = ((dimensions.y - top - 30.0) / 2.0).max(100.0);
+    let x = dimensions.x - NETWORK_PANEL_WIDTH - 10.0;
+    for (index, (network, title, labels)) in [
+        (&direction_ai, "Direction network", &DIRECTION_LABELS[..]),
+        (&shooting_ai, "Shooting network", &SHOOTING_LABELS[..]),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        network_view::draw_network(
+            d,
+            network,
+            &world.observation.rays,
+            title,
+            labels,
+            Rectangle {
+                x,
+                y: top + index as f32 * (height + 10.0),
+                width: NETWORK_PANEL_WIDTH,
+                height,
+            },
+        );
+    }
+}
+/// Draws the fitness of every generation, the selected AI's running score and the latest
+/// score of every AI along the bottom of the window.
+fn draw_chart_panel(
+    d: &mut raylib::prelude::RaylibDrawHandle<'_>,
+    shared_resources: &SharedResources,
+    fitness_trend: &FitnessTrend,
+    running_score: &RunningScore,
+) {
+    let selected_ai = { *lock_with_error!(shared_resources.selected_ai) };
+    let ai_scores = { lock_with_error!(shared_resources.ai_scores).clone() };
+    let height = { lock_with_error!(shared_resources.dimensions).y };
+    let area = |index: usize| Rectangle {
+        x: 10.0 + index as f32 * (CHART_WIDTH + 10.0),
+        y: height - CHART_HEIGHT - 10.0,
+        width: CHART_WIDTH,
+        height: CHART_HEIGHT,
+    };
+    charts::draw_line_chart(
+        d,
+        area(0),
+        "Fitness per generation",
+        &[
+            Series {
+                label: "best",
+                color: Color::DARKGREEN,
+                points: &fitness_trend.best,
+            },
+            Series {
+                label: "mean",
+                color: Color::BLUE,
+                points: &fitness_trend.mean,
+            },
+            Series {
+                label: "worst",
+                color: Color::MAROON,
+                points: &fitness_trend.worst,
+            },
+        ],
+    );
+    charts::draw_line_chart(
+        d,
+        area(1),
+        &format!("AI {selected_ai} episode score"),
+        &[Series {
+            label: "score",
+            color: Color::BLUE,
+            points: &running_score.points,
+        }],
+    );
+    charts::draw_bar_chart(d, area(2), "AI scores", &ai_scores, Some(selected_ai));
+}
+fn update_dimensions(rl: &RaylibHandle, shared_resources: &SharedResources) {
+    shared_resources.set_dimensions(rl.get_render_width() as f32, rl.get_render_height() as f32);
+}
@@ -2,6 +2,9 @@
 
 use std::f32::consts::PI;
 
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "render")]
 use raylib::{
     color::Color,
     ffi::Vector2,
@@ -10,16 +13,41 @@ use raylib::{
 
 use crate::TWO_PI;
 
-pub const CANNON_RADIUS: f32 = 50.0;
-pub const BARREL_HEIGHT: f32 = 40.0;
-const BARREL_WIDTH: f32 = 2.0 * BARREL_HEIGHT / 3.0;
+const CANNON_RADIUS: f32 = 50.0;
+const BARREL_HEIGHT: f32 = 40.0;
 const ENEMY_SIZE: usize = 10;
-pub const ENEMY_WIDTH: f32 = 7.5 * ENEMY_SIZE as f32;
-pub const ENEMY_HEIGHT: f32 = 10.0 * ENEMY_SIZE as f32;
+const ENEMY_WIDTH: f32 = 7.5 * ENEMY_SIZE as f32;
+const ENEMY_HEIGHT: f32 = 10.0 * ENEMY_SIZE as f32;
 const BULLET_SIZE: usize = 10;
 const BULLET_WIDTH: f32 = 1.5 * BULLET_SIZE as f32;
-pub const BULLET_HEIGHT: f32 = 2.5 * BULLET_SIZE as f32;
+const BULLET_HEIGHT: f32 = 2.5 * BULLET_SIZE as f32;
+
+/// Sizes of everything drawn in the arena, used for collisions as well as rendering.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EntitySizes {
+    pub cannon_radius: f32,
+    pub barrel_height: f32,
+    pub enemy_width: f32,
+    pub enemy_height: f32,
+    pub bullet_width: f32,
+    pub bullet_height: f32,
+}
 
+impl Default for EntitySizes {
+    fn default() -> Self {
+        Self {
+            cannon_radius: CANNON_RADIUS,
+            barrel_height: BARREL_HEIGHT,
+            enemy_width: ENEMY_WIDTH,
+            enemy_height: ENEMY_HEIGHT,
+            bullet_width: BULLET_WIDTH,
+            bullet_height: BULLET_HEIGHT,
+        }
+    }
+}
+
+/// A position or velocity in arena coordinates.
 #[derive(Clone)]
 pub struct Point {
     pub x: f32,
@@ -85,45 +113,53 @@ impl Point {
 pub trait Sprite {
     fn position(&self) -> &Point;
     fn position_mut(&mut self) -> &mut Point;
+    #[cfg(feature = "render")]
     fn draw(&self, d: &mut RaylibDrawHandle<'_>);
 }
 
+#[derive(Clone)]
 pub struct Cannon {
     pub position: Point,
     pub direction: f32,
+    pub radius: f32,
+    pub barrel_height: f32,
 }
 
 impl Cannon {
-    pub fn new() -> Self {
+    pub fn new(sizes: &EntitySizes) -> Self {
         Self {
             position: Point { x: 400.0, y: 300.0 },
             direction: 0.0,
+            radius: sizes.cannon_radius,
+            barrel_height: sizes.barrel_height,
         }
     }
 }
 
 impl Sprite for Cannon {
+    #[cfg(feature = "render")]
     fn draw(&self, d: &mut RaylibDrawHandle<'_>) {
-        const HALF_BARREL_HEIGHT: f32 = BARREL_HEIGHT / 2.0;
-        const HALF_BARREL_WIDTH: f32 = BARREL_WIDTH / 2.0;
+        let barrel_width = 2.0 * self.barrel_height / 3.0;
+        let half_barrel_height = self.barrel_height / 2.0;
+        let half_barrel_width = barrel_width / 2.0;
         d.draw_circle(
             self.position.x as i32,
             self.position.y as i32,
-            CANNON_RADIUS,
+            self.radius,
             Color::BLACK,
         );
         d.draw_rectangle_pro(
             raylib::ffi::Rectangle {
                 x: self.position.x
-                    + self.direction.cos() * (CANNON_RADIUS + HALF_BARREL_HEIGHT - 5.0),
+                    + self.direction.cos() * (self.radius + half_barrel_height - 5.0),
                 y: self.position.y
-                    + self.direction.sin() * (CANNON_RADIUS + HALF_BARREL_HEIGHT - 5.0),
-                width: BARREL_HEIGHT,
-                height: BARREL_WIDTH,
+                    + self.direction.sin() * (self.radius + half_barrel_height - 5.0),
+                width: self.barrel_height,
+                height: barrel_width,
             },
             Vector2 {
-                x: HALF_BARREL_HEIGHT,
-                y: HALF_BARREL_WIDTH,
+                x: half_barrel_height,
+                y: half_barrel_width,
             },
             self.direction * 180.0 / PI,
             Color::BLACK,
@@ -143,24 +179,28 @@ pub trait Entity {
     fn update(&mut self, delta_time: f32);
 }
 
+#[derive(Clone)]
 pub struct Bullet {
     pub position: Point,
     pub direction: f32,
     pub velocity: Point,
+    pub width: f32,
+    pub height: f32,
 }
 
 impl Sprite for Bullet {
+    #[cfg(feature = "render")]
     fn draw(&self, d: &mut RaylibDrawHandle<'_>) {
         d.draw_rectangle_pro(
             raylib::ffi::Rectangle {
                 x: self.position.x,
                 y: self.position.y,
-                width: BULLET_HEIGHT,
-                height: BULLET_WIDTH,
+                width: self.height,
+                height: self.width,
             },
             Vector2 {
-                x: BULLET_HEIGHT / 2.0,
-                y: BULLET_WIDTH / 2.0,
+                x: self.height / 2.0,
+                y: self.width / 2.0,
             },
             self.direction * 180.0 / PI,
             Color::BLACK,
@@ -182,36 +222,40 @@ impl Entity for Bullet {
             .sum_to_borrowed(&self.velocity.scale(delta_time));
     }
 }
+#[derive(Clone)]
 pub struct Enemy {
     pub position: Point,
     pub direction: f32,
     pub velocity: Point,
+    pub width: f32,
+    pub height: f32,
 }
 
 impl Sprite for Enemy {
+    #[cfg(feature = "render")]
     fn draw(&self, d: &mut RaylibDrawHandle<'_>) {
-        const HALF_ENEMY_HEIGHT: f32 = ENEMY_HEIGHT / 2.0;
-        const HALF_ENEMY_WIDTH: f32 = ENEMY_WIDTH / 2.0;
+        let half_enemy_height = self.height / 2.0;
+        let half_enemy_width = self.width / 2.0;
         let direction_cos = self.direction.cos();
         let direction_sin = self.direction.sin();
         d.draw_triangle(
             Vector2 {
-                x: self.position.x + direction_cos * HALF_ENEMY_HEIGHT,
-                y: self.position.y + direction_sin * HALF_ENEMY_HEIGHT,
+                x: self.position.x + direction_cos * half_enemy_height,
+                y: self.position.y + direction_sin * half_enemy_height,
             },
             Vector2 {
-                x: self.position.x - direction_cos * HALF_ENEMY_HEIGHT
-                    + direction_sin * HALF_ENEMY_WIDTH,
+                x: self.position.x - direction_cos * half_enemy_height
+                    + direction_sin * half_enemy_width,
                 y: self.position.y
-                    - direction_cos * HALF_ENEMY_WIDTH
-                    - direction_sin * HALF_ENEMY_HEIGHT,
+                    - direction_cos * half_enemy_width
+                    - direction_sin * half_enemy_height,
             },
             Vector2 {
                 x: self.position.x
-                    - direction_cos * HALF_ENEMY_HEIGHT
-                    - direction_sin * HALF_ENEMY_WIDTH,
-                y: self.position.y + direction_cos * HALF_ENEMY_WIDTH
-                    - direction_sin * HALF_ENEMY_HEIGHT,
+                    - direction_cos * half_enemy_height
+                    - direction_sin * half_enemy_width,
+                y: self.position.y + direction_cos * half_enemy_width
+                    - direction_sin * half_enemy_height,
             },
             Color::BLACK,
         );
@@ -0,0 +1,460 @@
+use std::{
+    f32::consts::PI,
+    ops::{AddAssign, ControlFlow},
+    sync::Arc,
+};
+
+use na::DVector;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    config::ExperimentConfig,
+    entity::{Bullet, Cannon, Enemy, Entity, Point},
+    neural_network::NeuralNetwork,
+    new_dynamic_array, HALF_PI, TWO_PI,
+};
+
+const KILL_REWARD: f32 = 1.0;
+const HIT_PENALTY: f32 = 1.0;
+const SHOT_PENALTY: f32 = 0.1;
+
+/// What the policy sees after every step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    /// Normalized distance to the enemy seen by each view ray, or 0 if the ray sees nothing.
+    pub rays: Box<[f32]>,
+    /// Whether a shoot action in the next step will actually fire.
+    pub can_shoot: bool,
+}
+
+/// Which way the cannon turns during a step, in the order of the direction network's outputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    CounterClockwise,
+    Hold,
+    Clockwise,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 3] = [
+        Rotation::CounterClockwise,
+        Rotation::Hold,
+        Rotation::Clockwise,
+    ];
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn sign(self) -> f32 {
+        self.index() as f32 - 1.0
+    }
+}
+
+/// The decision a policy makes for one step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Action {
+    pub rotation: Rotation,
+    pub shoot: bool,
+}
+
+/// Shape and bounds of [`Observation::rays`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObservationSpace {
+    pub total_rays: usize,
+    pub ray_low: f32,
+    pub ray_high: f32,
+}
+
+/// Number of choices for each part of an [`Action`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionSpace {
+    pub rotations: usize,
+    pub shoot_options: usize,
+}
+
+/// Running statistics of the current episode.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepInfo {
+    pub score: f32,
+    pub elapsed_time: f32,
+    pub enemies_spawned: usize,
+    pub enemies_killed: usize,
+    pub enemies_reached_cannon: usize,
+    pub bullets_fired: usize,
+}
+
+impl AddAssign<&StepInfo> for StepInfo {
+    /// Accumulates the counters of several episodes.
+    fn add_assign(&mut self, other: &StepInfo) {
+        self.score += other.score;
+        self.elapsed_time += other.elapsed_time;
+        self.enemies_spawned += other.enemies_spawned;
+        self.enemies_killed += other.enemies_killed;
+        self.enemies_reached_cannon += other.enemies_reached_cannon;
+        self.bullets_fired += other.bullets_fired;
+    }
+}
+
+/// A copy of one AI's world, published for the renderer so that it never touches the world a
+/// worker is simulating.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    pub ai_index: usize,
+    pub cannon: Cannon,
+    pub bullets: Vec<Bullet>,
+    pub enemies: Vec<Enemy>,
+    pub observation: Observation,
+    pub info: StepInfo,
+}
+
+/// Anything that can control the cannon: a network, a script or an external learner.
+pub trait Policy {
+    fn act(&mut self, observation: &Observation) -> Action;
+}
+
+/// Drives the cannon with a direction network (3 outputs) and a shooting network (2 outputs).
+pub struct NetworkPolicy<'a> {
+    pub direction_ai: &'a NeuralNetwork,
+    pub shooting_ai: &'a NeuralNetwork,
+}
+
+impl NetworkPolicy<'_> {
+    pub const DIRECTION_OUTPUTS: usize = 3;
+    pub const SHOOTING_OUTPUTS: usize = 2;
+}
+
+impl Policy for NetworkPolicy<'_> {
+    fn act(&mut self, observation: &Observation) -> Action {
+        let input = DVector::from_column_slice(&observation.rays);
+        let rotation =
+            find_largest_index_unchecked(self.direction_ai.run_unchecked(&input).as_slice());
+        let shoot = observation.can_shoot
+            && find_largest_index_unchecked(self.shooting_ai.run_unchecked(&input).as_slice()) == 0;
+        Action {
+            rotation: Rotation::from_index(rotation).unwrap_or(Rotation::Hold),
+            shoot,
+        }
+    }
+}
+
+/// One cannon defending itself for `training_time` seconds of fixed-step simulation.
+#[derive(Clone)]
+pub struct CannonEnv {
+    config: Arc<ExperimentConfig>,
+    dimensions: Point,
+    rng: StdRng,
+    cannon: Cannon,
+    bullets: Vec<Bullet>,
+    enemies: Vec<Enemy>,
+    time_since_enemy: f32,
+    time_since_bullet: f32,
+    info: StepInfo,
+    observation: Observation,
+}
+
+impl CannonEnv {
+    pub fn new(config: Arc<ExperimentConfig>, dimensions: Point) -> Self {
+        let mut env = Self {
+            cannon: Cannon::new(&config.entity_sizes),
+            rng: StdRng::seed_from_u64(0),
+            bullets: vec![],
+            enemies: vec![],
+            time_since_enemy: config.enemy_cooldown,
+            time_since_bullet: 0.0,
+            info: StepInfo::default(),
+            observation: Observation {
+                rays: new_dynamic_array!(config.total_view_rays, 0.0, f32),
+                can_shoot: false,
+            },
+            dimensions,
+            config,
+        };
+        env.cannon.position = env.center();
+        env
+    }
+    pub fn observation_space(&self) -> ObservationSpace {
+        ObservationSpace {
+            total_rays: self.config.total_view_rays,
+            ray_low: 0.0,
+            ray_high: 1.0,
+        }
+    }
+    pub fn action_space(&self) -> ActionSpace {
+        ActionSpace {
+            rotations: Rotation::ALL.len(),
+            shoot_options: 2,
+        }
+    }
+    /// Starts a new episode whose enemy spawns are fully determined by `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.cannon = Cannon::new(&self.config.entity_sizes);
+        self.cannon.position = self.center();
+        self.bullets.clear();
+        self.enemies.clear();
+        self.time_since_enemy = self.config.enemy_cooldown;
+        self.time_since_bullet = 0.0;
+        self.info = StepInfo::default();
+        self.observation = self.observe();
+        self.observation.clone()
+    }
+    /// Advances the world by one fixed time step, returning the new observation, the reward
+    /// earned during the step, whether the episode is over and the running episode statistics.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool, StepInfo) {
+        let delta_time = self.config.fast_delta_time;
+        let score_before = self.info.score;
+
+        self.time_since_enemy += delta_time;
+        self.time_since_bullet += delta_time;
+        self.info.elapsed_time += delta_time;
+
+        if self.time_since_enemy >= self.config.enemy_cooldown {
+            self.time_since_enemy = 0.0;
+            self.spawn_rand_enemy();
+        }
+        if action.shoot && self.time_since_bullet >= self.config.bullet_cooldown {
+            self.time_since_bullet = 0.0;
+            self.info.score -= SHOT_PENALTY;
+            self.spawn_bullet();
+        }
+        self.update_entities(action.rotation, delta_time);
+        self.destroy_entities();
+
+        self.observation = self.observe();
+        (
+            self.observation.clone(),
+            self.info.score - score_before,
+            self.is_done(),
+            self.info.clone(),
+        )
+    }
+    /// Runs a whole episode with `policy` and returns the final statistics.
+    pub fn run_episode(&mut self, seed: u64, policy: &mut impl Policy) -> StepInfo {
+        self.run_episode_with(seed, policy, |_, _| ControlFlow::Continue(()))
+            .expect("Episodes only stop early when the step callback breaks")
+    }
+    /// Like [`Self::run_episode`], but calls `on_step` with the world and the action taken after
+    /// every step. Returns `None` if `on_step` breaks off the episode before it is done.
+    pub fn run_episode_with(
+        &mut self,
+        seed: u64,
+        policy: &mut impl Policy,
+        mut on_step: impl FnMut(&CannonEnv, Action) -> ControlFlow<()>,
+    ) -> Option<StepInfo> {
+        let mut observation = self.reset(seed);
+        loop {
+            let action = policy.act(&observation);
+            let (next_observation, _, done, info) = self.step(action);
+            if on_step(self, action).is_break() {
+                return None;
+            }
+            if done {
+                return Some(info);
+            }
+            observation = next_observation;
+        }
+    }
+    pub fn snapshot(&self, ai_index: usize) -> WorldSnapshot {
+        WorldSnapshot {
+            ai_index,
+            cannon: self.cannon.clone(),
+            bullets: self.bullets.clone(),
+            enemies: self.enemies.clone(),
+            observation: self.observation.clone(),
+            info: self.info.clone(),
+        }
+    }
+    pub fn is_done(&self) -> bool {
+        self.info.elapsed_time > self.config.training_time
+    }
+    pub fn config(&self) -> &ExperimentConfig {
+        &self.config
+    }
+    pub fn observation(&self) -> &Observation {
+        &self.observation
+    }
+    pub fn info(&self) -> &StepInfo {
+        &self.info
+    }
+    pub fn cannon(&self) -> &Cannon {
+        &self.cannon
+    }
+    pub fn bullets(&self) -> &[Bullet] {
+        &self.bullets
+    }
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+    pub fn dimensions(&self) -> &Point {
+        &self.dimensions
+    }
+    pub fn set_dimensions(&mut self, dimensions: Point) {
+        self.dimensions = dimensions;
+        self.cannon.position = self.center();
+    }
+    fn center(&self) -> Point {
+        Point {
+            x: self.dimensions.x / 2.0,
+            y: self.dimensions.y / 2.0,
+        }
+    }
+    fn observe(&self) -> Observation {
+        let config = &*self.config;
+        let total_view_rays = config.total_view_rays;
+        let mut known_enemy_locations = new_dynamic_array!(total_view_rays, 0.0, f32);
+        let center = self.center();
+        let direction = self.cannon.direction;
+        for (i, known_enemy_location) in known_enemy_locations.iter_mut().enumerate() {
+            let angle = view_ray_angle(direction, i, total_view_rays);
+            for enemy in self.enemies.iter() {
+                let relative_position = enemy.position.difference(&center);
+                let distance = relative_position.magnitude();
+                if distance > config.view_ray_length {
+                    continue;
+                }
+
+                let location_direction = relative_position.arc_tan();
+                let delta_angle = angle - location_direction;
+                if delta_angle.abs() < HALF_PI
+                    && distance * delta_angle.sin().abs() <= enemy.width / 2.0
+                {
+                    *known_enemy_location =
+                        (distance - config.entity_sizes.cannon_radius) / config.view_ray_length;
+                    break;
+                }
+            }
+        }
+        Observation {
+            rays: known_enemy_locations,
+            can_shoot: self.time_since_bullet + config.fast_delta_time >= config.bullet_cooldown,
+        }
+    }
+    fn destroy_entities(&mut self) {
+        let dimensions = &self.dimensions;
+        let enemies = &mut self.enemies;
+        let bullets = &mut self.bullets;
+        let mut i = 0;
+        'bullet: while i < bullets.len() {
+            let bullet_pos = &bullets[i].position;
+            if bullet_pos.x < 0.0
+                || bullet_pos.x > dimensions.x
+                || bullet_pos.y < 0.0
+                || bullet_pos.y > dimensions.y
+            {
+                bullets.remove(i);
+            } else {
+                let mut j = 0;
+                while j < enemies.len() {
+                    let enemy_pos = &enemies[j].position;
+                    if bullet_pos.difference(enemy_pos).magnitude()
+                        <= (bullets[i].height + enemies[j].height) / 2.0
+                    {
+                        bullets.remove(i);
+                        enemies.remove(j);
+                        self.info.score += KILL_REWARD;
+                        self.info.enemies_killed += 1;
+                        continue 'bullet;
+                    } else {
+                        j += 1;
+                    }
+                }
+                i += 1;
+            }
+        }
+
+        let center = self.center();
+        let cannon_radius = self.config.entity_sizes.cannon_radius;
+        let mut i = 0;
+        while i < self.enemies.len() {
+            let center_distance = self.enemies[i].position.difference(&center).magnitude();
+            if center_distance < cannon_radius + self.enemies[i].height / 2.0 {
+                self.enemies.remove(i);
+                self.info.score -= HIT_PENALTY;
+                self.info.enemies_reached_cannon += 1;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    fn spawn_bullet(&mut self) {
+        let config = &*self.config;
+        let sizes = &config.entity_sizes;
+        let direction = self.cannon.direction;
+        let (direction_cos, direction_sin) = (direction.cos(), direction.sin());
+        let center = self.center();
+        self.bullets.push(Bullet {
+            position: Point {
+                x: center.x + direction_cos * (sizes.cannon_radius + sizes.barrel_height),
+                y: center.y + direction_sin * (sizes.cannon_radius + sizes.barrel_height),
+            },
+            direction,
+            velocity: Point {
+                x: direction_cos * config.bullet_speed,
+                y: direction_sin * config.bullet_speed,
+            },
+            width: sizes.bullet_width,
+            height: sizes.bullet_height,
+        });
+        self.info.bullets_fired += 1;
+    }
+    fn spawn_rand_enemy(&mut self) {
+        let config = &*self.config;
+        let center = self.center();
+        let location_direction = self.rng.gen_range(0.0..TWO_PI);
+        let facing_direction = PI + location_direction;
+        let spawn_distance = config.view_ray_length + config.enemy_spawn_distance;
+        self.enemies.push(Enemy {
+            position: Point {
+                x: center.x + location_direction.cos() * spawn_distance,
+                y: center.y + location_direction.sin() * spawn_distance,
+            },
+            direction: facing_direction,
+            velocity: Point {
+                x: config.enemy_speed * facing_direction.cos(),
+                y: config.enemy_speed * facing_direction.sin(),
+            },
+            width: config.entity_sizes.enemy_width,
+            height: config.entity_sizes.enemy_height,
+        });
+        self.info.enemies_spawned += 1;
+    }
+    fn update_entities(&mut self, rotation: Rotation, delta_time: f32) {
+        let delta_direction = rotation.sign() * self.config.gun_rotate_velocity * delta_time;
+        self.cannon.direction += delta_direction;
+        while self.cannon.direction >= TWO_PI {
+            self.cannon.direction -= TWO_PI;
+        }
+        self.info.score -= delta_direction.abs() / TWO_PI;
+
+        for enemy in self.enemies.iter_mut() {
+            enemy.update(delta_time);
+        }
+        for bullet in self.bullets.iter_mut() {
+            bullet.update(delta_time);
+        }
+    }
+}
+
+/// Angle of view ray `ray` for a cannon facing `direction`. The rays are spread evenly around
+/// the cannon, starting behind it, so ray `total_rays / 2` points where the cannon faces.
+pub fn view_ray_angle(direction: f32, ray: usize, total_rays: usize) -> f32 {
+    let mut angle = direction + TWO_PI * ray as f32 / total_rays as f32 - PI;
+    if angle >= TWO_PI {
+        angle -= TWO_PI;
+    }
+    if angle < 0.0 {
+        angle += TWO_PI;
+    }
+    angle
+}
+pub(crate) fn find_largest_index_unchecked(values: &[f32]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(index, _)| index)
+        .unwrap()
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// How the scores of one AI's episodes are combined into the fitness used for selection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FitnessAggregate {
+    #[default]
+    Mean,
+    Median,
+    /// `mean - z * standard_error`, which penalizes AIs whose scores vary a lot between episodes.
+    LowerConfidenceBound {
+        z: f32,
+    },
+}
+
+impl FitnessAggregate {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            FitnessAggregate::LowerConfidenceBound { z } if !(z.is_finite() && z >= 0.0) => Err(
+                format!("Lower confidence bound z must not be negative, got {z}"),
+            ),
+            _ => Ok(()),
+        }
+    }
+    /// Combines `scores`, returning `f32::MIN` when there are none.
+    pub fn aggregate(&self, scores: &[f32]) -> f32 {
+        if scores.is_empty() {
+            return f32::MIN;
+        }
+        match *self {
+            FitnessAggregate::Mean => mean(scores),
+            FitnessAggregate::Median => median(scores),
+            FitnessAggregate::LowerConfidenceBound { z } => {
+                mean(scores) - z * standard_deviation(scores) / (scores.len() as f32).sqrt()
+            }
+        }
+    }
+}
+
+pub fn mean(values: &[f32]) -> f32 {
+    values.iter().sum::<f32>() / values.len() as f32
+}
+pub fn median(values: &[f32]) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+/// Sample standard deviation, or 0 for fewer than two values.
+pub fn standard_deviation(values: &[f32]) -> f32 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / (values.len() - 1) as f32;
+    variance.sqrt()
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How the weights and biases of a new [`crate::neural_network::NeuralNetwork`] are drawn.
+///
+/// Xavier and He scale the weights by the layer's fan-in and fan-out and start every bias at
+/// zero; the explicit distributions draw biases from the same distribution as the weights.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Initializer {
+    /// Uniform in `[-limit, limit)`.
+    UniformSymmetric {
+        limit: f32,
+    },
+    /// Glorot uniform, in `[-sqrt(6 / (fan_in + fan_out)), sqrt(6 / (fan_in + fan_out)))`.
+    #[default]
+    Xavier,
+    /// Normal with a standard deviation of `sqrt(2 / fan_in)`, suited to ReLU layers.
+    He,
+    /// Normal with mean 0 and the given standard deviation.
+    Normal {
+        std: f32,
+    },
+    Zeros,
+}
+
+impl Initializer {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Initializer::UniformSymmetric { limit } if !(limit.is_finite() && limit > 0.0) => Err(
+                format!("Uniform initializer limit must be positive, got {limit}"),
+            ),
+            Initializer::Normal { std } if !(std.is_finite() && std > 0.0) => Err(format!(
+                "Normal initializer standard deviation must be positive, got {std}"
+            )),
+            _ => Ok(()),
+        }
+    }
+    pub fn weight(&self, fan_in: usize, fan_out: usize, rng: &mut impl Rng) -> f32 {
+        match *self {
+            Initializer::UniformSymmetric { limit } => rng.gen_range(-limit..limit),
+            Initializer::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out) as f32).sqrt();
+                rng.gen_range(-limit..limit)
+            }
+            Initializer::He => sample_normal((2.0 / fan_in as f32).sqrt(), rng),
+            Initializer::Normal { std } => sample_normal(std, rng),
+            Initializer::Zeros => 0.0,
+        }
+    }
+    pub fn bias(&self, rng: &mut impl Rng) -> f32 {
+        match *self {
+            Initializer::UniformSymmetric { limit } => rng.gen_range(-limit..limit),
+            Initializer::Normal { std } => sample_normal(std, rng),
+            Initializer::Xavier | Initializer::He | Initializer::Zeros => 0.0,
+        }
+    }
+}
+
+/// Box-Muller transform, so that no extra distribution crate is needed.
+fn sample_normal(std: f32, rng: &mut impl Rng) -> f32 {
+    let uniform: f32 = 1.0 - rng.gen::<f32>();
+    let angle: f32 = rng.gen_range(0.0..2.0 * PI);
+    std * (-2.0 * uniform.ln()).sqrt() * angle.cos()
+}
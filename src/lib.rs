@@ -0,0 +1,58 @@
+//! Evolves neural networks that defend a cannon against incoming enemies.
+//!
+//! - [`neural_network::NeuralNetwork`] is the feed-forward network that controls the cannon.
+//! - [`environment::CannonEnv`] is a self-contained episode with `reset`/`step`, driven by any
+//!   [`environment::Policy`].
+//! - [`trainer`] runs a [`multi_threading::SharedResources`] population through the environment
+//!   and evolves it between generations.
+//! - `display`, `charts`, `network_view`, `play`, `replay_viewer`, `ui` and `world_view` draw the
+//!   simulation with raylib and are only built with the `render` feature.
+//! - `cli` holds the command line arguments of the binaries and is only built with the `cli`
+//!   feature, which is the only part of the library that needs clap.
+
+#[macro_export]
+macro_rules! lock_with_error {
+    ($var:expr) => {
+        $var.lock()
+            .expect(&format!("Failed to lock {} mutex", stringify!($var)))
+    };
+}
+#[cfg(feature = "render")]
+pub mod charts;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod compare;
+pub mod config;
+pub mod demonstration;
+#[cfg(feature = "render")]
+pub mod display;
+pub mod entity;
+pub mod environment;
+pub mod fitness;
+pub mod initializer;
+pub mod metrics;
+pub mod multi_threading;
+#[cfg(feature = "render")]
+pub mod network_view;
+pub mod neural_network;
+pub mod onnx;
+pub mod optimizer;
+#[cfg(feature = "render")]
+pub mod play;
+pub mod replay;
+#[cfg(feature = "render")]
+pub mod replay_viewer;
+pub mod seeding;
+pub mod selection;
+pub mod storage;
+pub mod trainer;
+#[cfg(feature = "render")]
+pub mod ui;
+pub mod worker_pool;
+#[cfg(feature = "render")]
+pub mod world_view;
+
+use std::f32::consts::PI;
+
+pub const TWO_PI: f32 = 2.0 * PI;
+pub const HALF_PI: f32 = PI / 2.0;
@@ -0,0 +1,133 @@
+//! Per-generation training records, appended to a CSV or JSON Lines file so that learning
+//! curves can be plotted and runs compared.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::StepInfo,
+    fitness::{mean, median, standard_deviation},
+};
+
+/// Column order of the CSV format, matching the fields of [`GenerationMetrics`].
+pub const CSV_HEADER: &str = "generation,min_score,mean_score,median_score,max_score,std_dev_score,enemies_killed,bullets_fired,enemies_escaped,wall_time_secs,config_hash";
+
+/// Summary of one generation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationMetrics {
+    pub generation: usize,
+    pub min_score: f32,
+    pub mean_score: f32,
+    pub median_score: f32,
+    pub max_score: f32,
+    pub std_dev_score: f32,
+    pub enemies_killed: usize,
+    pub bullets_fired: usize,
+    /// Enemies that reached the cannon.
+    pub enemies_escaped: usize,
+    /// Seconds of training at the end of this generation, counting every session of a resumed
+    /// run.
+    pub wall_time_secs: f64,
+    /// [`crate::config::ExperimentConfig::hash`] of the run, in hex.
+    pub config_hash: String,
+}
+
+impl GenerationMetrics {
+    /// Summarizes the fitness of every AI and the episode totals of every AI.
+    pub fn new(
+        generation: usize,
+        ai_scores: &[f32],
+        episode_totals: &[StepInfo],
+        wall_time: Duration,
+        config_hash: u64,
+    ) -> Self {
+        Self {
+            generation,
+            min_score: ai_scores.iter().cloned().fold(f32::MAX, f32::min),
+            mean_score: mean(ai_scores),
+            median_score: median(ai_scores),
+            max_score: ai_scores.iter().cloned().fold(f32::MIN, f32::max),
+            std_dev_score: standard_deviation(ai_scores),
+            enemies_killed: episode_totals.iter().map(|info| info.enemies_killed).sum(),
+            bullets_fired: episode_totals.iter().map(|info| info.bullets_fired).sum(),
+            enemies_escaped: episode_totals
+                .iter()
+                .map(|info| info.enemies_reached_cannon)
+                .sum(),
+            wall_time_secs: wall_time.as_secs_f64(),
+            config_hash: format!("{config_hash:016x}"),
+        }
+    }
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            self.generation,
+            self.min_score,
+            self.mean_score,
+            self.median_score,
+            self.max_score,
+            self.std_dev_score,
+            self.enemies_killed,
+            self.bullets_fired,
+            self.enemies_escaped,
+            self.wall_time_secs,
+            self.config_hash
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricsFormat {
+    Csv,
+    JsonLines,
+}
+
+impl MetricsFormat {
+    /// `.csv` is CSV, `.jsonl` and `.json` are JSON Lines.
+    pub fn from_path(path: &Path) -> Result<Self, io::Error> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(MetricsFormat::Csv),
+            Some("jsonl") | Some("json") => Ok(MetricsFormat::JsonLines),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Metrics file {} must end in .csv or .jsonl", path.display()),
+            )),
+        }
+    }
+}
+
+/// An append-only metrics file. Every record is flushed as it is written, so the file can be
+/// plotted while training runs and survives a crash.
+pub struct MetricsLog {
+    file: File,
+    format: MetricsFormat,
+}
+
+impl MetricsLog {
+    /// Opens `path` for appending, writing the CSV header if the file is new or empty.
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        let format = MetricsFormat::from_path(path)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if format == MetricsFormat::Csv && file.metadata()?.len() == 0 {
+            writeln!(file, "{CSV_HEADER}")?;
+        }
+        Ok(Self { file, format })
+    }
+    pub fn format(&self) -> MetricsFormat {
+        self.format
+    }
+    pub fn append(&mut self, metrics: &GenerationMetrics) -> Result<(), io::Error> {
+        let line = match self.format {
+            MetricsFormat::Csv => metrics.to_csv_row(),
+            MetricsFormat::JsonLines => serde_json::to_string(metrics)?,
+        };
+        writeln!(self.file, "{line}")?;
+        self.file.flush()
+    }
+}
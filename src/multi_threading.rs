@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 #[macro_export]
 macro_rules! new_arc_mutex {
     ($val:expr) => {
@@ -19,138 +17,471 @@ macro_rules! new_dynamic_array {
 }
 
 use std::{
-    fs::File,
-    io::{self, Read, Write},
+    fs, io,
     num::NonZero,
-    path::Path,
-    sync::{atomic::AtomicBool, Arc, Mutex},
-    thread::available_parallelism,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    entity::{Bullet, Cannon, Enemy, Point},
+    config::ExperimentConfig,
+    entity::Point,
+    environment::{NetworkPolicy, StepInfo, WorldSnapshot},
     neural_network::NeuralNetwork,
-    TOTAL_VIEW_RAYS,
+    replay::Replay,
+    seeding::{stream_rng, RngStream},
+    storage::{self, LoadError, MismatchPolicy, NetworkFormat},
+    worker_pool::WorkerPool,
 };
 
+/// Where a population is loaded from and saved to; `None` picks a name based on its size.
+#[derive(Default)]
+pub struct PopulationFiles {
+    pub direction_path: Option<PathBuf>,
+    pub shooting_path: Option<PathBuf>,
+    pub checkpoint_path: Option<PathBuf>,
+    /// What to do when the saved networks do not fit the run.
+    pub on_mismatch: MismatchPolicy,
+    /// How the network files are written; loading detects the format.
+    pub format: NetworkFormat,
+}
+
+/// Everything needed to continue training exactly where it stopped. Every random draw comes
+/// from [`stream_rng`], so `seed` and `generation` are the whole RNG state.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub config: ExperimentConfig,
+    pub seed: u64,
+    /// The generation to play next.
+    #[serde(default)]
+    pub generation: usize,
+    /// Fitness of every AI in each completed generation.
+    #[serde(default)]
+    pub fitness_history: Vec<Box<[f32]>>,
+    /// Seconds spent training up to `generation`, over every session.
+    #[serde(default)]
+    pub wall_time_secs: f64,
+    pub direction_ais: Box<[NeuralNetwork]>,
+    pub shooting_ais: Box<[NeuralNetwork]>,
+}
+
+impl Checkpoint {
+    /// Reads a checkpoint and checks the config saved in it.
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        let invalid_checkpoint = |error: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid checkpoint {}: {error}", path.display()),
+            )
+        };
+        let json = fs::read_to_string(path)?;
+        let checkpoint: Self =
+            serde_json::from_str(&json).map_err(|error| invalid_checkpoint(error.to_string()))?;
+        checkpoint.config.validate().map_err(invalid_checkpoint)?;
+        Ok(checkpoint)
+    }
+}
+
+/// The population and its settings, shared between the trainer and display threads. Workers
+/// own the worlds they simulate and only publish snapshots of the selected one.
 #[derive(Clone)]
 pub struct SharedResources {
+    pub config: Arc<ExperimentConfig>,
+    pub seed: u64,
+    pub generation: Arc<AtomicUsize>,
     pub total_ais: Arc<NonZero<usize>>,
     pub is_running: Arc<AtomicBool>,
     pub is_real_time: Arc<AtomicBool>,
     pub dimensions: Arc<Mutex<Point>>,
-    pub elapsed_simulation_times: Arc<Mutex<Box<[f32]>>>,
     pub selected_ai: Arc<Mutex<usize>>,
+    /// Aggregated fitness of every AI in the last generation.
     pub ai_scores: Arc<Mutex<Box<[f32]>>>,
+    /// `ai_scores` of every completed generation, oldest first.
+    pub fitness_history: Arc<Mutex<Vec<Box<[f32]>>>>,
+    /// Training time up to the last completed generation, including sessions before a resume.
+    pub wall_time: Arc<Mutex<Duration>>,
+    /// Score of every episode each AI played in the last generation.
+    pub episode_scores: Arc<Mutex<Box<[Vec<f32>]>>>,
+    /// Kills, shots and escapes of every AI summed over its episodes in the last generation.
+    pub episode_totals: Arc<Mutex<Box<[StepInfo]>>>,
+    /// The highest scoring episode of every AI in the last generation, recorded as it was played.
+    pub best_episodes: Arc<Mutex<Box<[Option<Replay>]>>>,
     pub direction_ais: Arc<Mutex<Box<[NeuralNetwork]>>>,
     pub shooting_ais: Arc<Mutex<Box<[NeuralNetwork]>>>,
-    pub cannons: Arc<Mutex<Box<[Cannon]>>>,
-    pub bullets: Arc<Mutex<Box<[Vec<Bullet>]>>>,
-    pub enemies: Arc<Mutex<Box<[Vec<Enemy>]>>>,
+    /// The latest world of the selected AI, written by whichever worker is playing it.
+    pub selected_world: Arc<Mutex<Option<WorldSnapshot>>>,
+    pub worker_pool: Arc<WorkerPool>,
+    pub direction_path: Arc<PathBuf>,
+    pub shooting_path: Arc<PathBuf>,
+    pub checkpoint_path: Arc<PathBuf>,
+    pub network_format: NetworkFormat,
 }
 
 impl SharedResources {
-    pub fn new() -> Result<Self, io::Error> {
-        let total_ais = {
-            let mut total_ais = available_parallelism()?;
-            if total_ais > unsafe { NonZero::new_unchecked(1) } {
-                total_ais = unsafe { NonZero::new_unchecked(Into::<usize>::into(total_ais) - 1) };
-            }
-            if Into::<usize>::into(total_ais) % 2 == 0 {
-                total_ais
-            } else if Into::<usize>::into(total_ais) < 10 {
-                NonZero::new(10_usize).expect("Computational error")
-            } else {
-                NonZero::new(Into::<usize>::into(total_ais) - 1).expect("Computational error")
+    /// Loads the saved population if its files exist, or creates a random one from `seed`. The
+    /// population size comes from `total_ais`, then the saved files, then the config, and never
+    /// from the hardware; `workers` only sets how many threads play it.
+    pub fn new(
+        config: ExperimentConfig,
+        seed: u64,
+        total_ais: Option<NonZero<usize>>,
+        workers: Option<NonZero<usize>>,
+        files: PopulationFiles,
+    ) -> Result<Self, io::Error> {
+        config
+            .validate()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let requested_total_ais = total_ais;
+        let total_ais = match requested_total_ais {
+            Some(total_ais) => total_ais,
+            None => NonZero::new(config.population_size)
+                .expect("Config validation guarantees a non-zero population_size"),
+        };
+        let on_mismatch = files.on_mismatch;
+        let network_format = files.format;
+        let [direction_path, shooting_path, checkpoint_path] = files.resolve(total_ais);
+
+        let (direction_ais, shooting_ais) = match load_saved_ais(
+            &config,
+            requested_total_ais,
+            &direction_path,
+            &shooting_path,
+            on_mismatch,
+        ) {
+            Ok(saved_ais) => saved_ais,
+            Err(error) if on_mismatch == MismatchPolicy::Random => {
+                eprintln!("Warning: {error}; starting from a random population");
+                for path in [&direction_path, &shooting_path] {
+                    if let Some(backup_path) = storage::back_up(path)? {
+                        eprintln!(
+                            "Moved {} to {} so that it is not overwritten",
+                            path.display(),
+                            backup_path.display()
+                        );
+                    }
+                }
+                (None, None)
             }
+            Err(error) => return Err(error.into()),
         };
-        Ok(Self {
+        let total_ais = direction_ais
+            .as_ref()
+            .or(shooting_ais.as_ref())
+            .and_then(|saved_ais| NonZero::new(saved_ais.len()))
+            .unwrap_or(total_ais);
+
+        let direction_ais = direction_ais.unwrap_or_else(|| {
+            new_random_ais(
+                &config,
+                seed,
+                total_ais.into(),
+                NetworkPolicy::DIRECTION_OUTPUTS,
+                RngStream::DirectionAis,
+            )
+        });
+        let shooting_ais = shooting_ais.unwrap_or_else(|| {
+            new_random_ais(
+                &config,
+                seed,
+                total_ais.into(),
+                NetworkPolicy::SHOOTING_OUTPUTS,
+                RngStream::ShootingAis,
+            )
+        });
+        Ok(Self::from_parts(
+            Checkpoint {
+                config,
+                seed,
+                generation: 0,
+                fitness_history: vec![],
+                wall_time_secs: 0.0,
+                direction_ais,
+                shooting_ais,
+            },
+            workers,
+            [direction_path, shooting_path, checkpoint_path],
+            network_format,
+        ))
+    }
+    /// Continues the population saved in `checkpoint`, which must hold `total_ais` AIs if that is
+    /// given. Later saves go to `files`, named after the checkpoint's population size.
+    pub fn from_checkpoint(
+        checkpoint: Checkpoint,
+        total_ais: Option<NonZero<usize>>,
+        workers: Option<NonZero<usize>>,
+        files: PopulationFiles,
+    ) -> Result<Self, io::Error> {
+        let invalid_data = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+        checkpoint
+            .config
+            .validate()
+            .map_err(|error| invalid_data(format!("Checkpoint config: {error}")))?;
+        let Some(saved_total_ais) = NonZero::new(checkpoint.direction_ais.len()) else {
+            return Err(invalid_data("Checkpoint holds no AIs".to_string()));
+        };
+        if checkpoint.shooting_ais.len() != usize::from(saved_total_ais) {
+            return Err(invalid_data(format!(
+                "Checkpoint holds {} direction AIs but {} shooting AIs",
+                saved_total_ais,
+                checkpoint.shooting_ais.len()
+            )));
+        }
+        if total_ais.is_some_and(|total_ais| total_ais != saved_total_ais) {
+            return Err(invalid_data(format!(
+                "Checkpoint holds {saved_total_ais} AIs but {} were requested",
+                total_ais.unwrap()
+            )));
+        }
+        if Duration::try_from_secs_f64(checkpoint.wall_time_secs).is_err() {
+            return Err(invalid_data(format!(
+                "Checkpoint wall time {} is not a valid duration",
+                checkpoint.wall_time_secs
+            )));
+        }
+        let network_format = files.format;
+        let paths = files.resolve(saved_total_ais);
+        storage::check_networks(
+            &paths[2],
+            &checkpoint.direction_ais,
+            checkpoint.config.total_view_rays,
+            NetworkPolicy::DIRECTION_OUTPUTS,
+        )?;
+        storage::check_networks(
+            &paths[2],
+            &checkpoint.shooting_ais,
+            checkpoint.config.total_view_rays,
+            NetworkPolicy::SHOOTING_OUTPUTS,
+        )?;
+        Ok(Self::from_parts(checkpoint, workers, paths, network_format))
+    }
+    fn from_parts(
+        checkpoint: Checkpoint,
+        workers: Option<NonZero<usize>>,
+        [direction_path, shooting_path, checkpoint_path]: [PathBuf; 3],
+        network_format: NetworkFormat,
+    ) -> Self {
+        let total_ais =
+            NonZero::new(checkpoint.direction_ais.len()).expect("Populations are never empty");
+        let config = Arc::new(checkpoint.config);
+        Self {
             total_ais: Arc::new(total_ais),
             is_running: new_arc_atomic_bool!(true),
             is_real_time: new_arc_atomic_bool!(true),
+            selected_world: new_arc_mutex!(None),
             dimensions: new_arc_mutex!(Point { x: 800.0, y: 600.0 }),
-            elapsed_simulation_times: new_arc_mutex!(new_dynamic_array!(
-                total_ais.into(),
-                0.0,
-                f32
+            worker_pool: Arc::new(WorkerPool::new(
+                workers.unwrap_or_else(WorkerPool::default_size),
             )),
             selected_ai: new_arc_mutex!(0),
             ai_scores: new_arc_mutex!(new_dynamic_array!(total_ais.into(), 0.0, f32)),
-            direction_ais: {
-                let file_name = format!("direction_ais_{}.json", Into::<usize>::into(total_ais));
-                if Path::new(file_name.as_str()).exists() {
-                    let mut file = File::open(file_name.clone())?;
-                    let mut json = String::new();
-                    file.read_to_string(&mut json)?;
-                    new_arc_mutex!(serde_json::from_str(&json)
-                        .unwrap_or_else(|_| panic!("Cannot read file {file_name}")))
-                } else {
-                    new_arc_mutex!(new_dynamic_array!(
-                        total_ais.into(),
-                        NeuralNetwork::new_random_unchecked(&[
-                            TOTAL_VIEW_RAYS,
-                            TOTAL_VIEW_RAYS / 2,
-                            3
-                        ]),
-                        NeuralNetwork
-                    ))
-                }
-            },
-            shooting_ais: {
-                let file_name = format!("shooting_ais_{}.json", Into::<usize>::into(total_ais));
-                if Path::new(file_name.as_str()).exists() {
-                    let mut file = File::open(file_name.clone())?;
-                    let mut json = String::new();
-                    file.read_to_string(&mut json)?;
-                    new_arc_mutex!(serde_json::from_str(&json)
-                        .unwrap_or_else(|_| panic!("Cannot read file {file_name}")))
-                } else {
-                    new_arc_mutex!(new_dynamic_array!(
-                        total_ais.into(),
-                        NeuralNetwork::new_random_unchecked(&[
-                            TOTAL_VIEW_RAYS,
-                            TOTAL_VIEW_RAYS / 2,
-                            2
-                        ]),
-                        NeuralNetwork
-                    ))
-                }
-            },
-            cannons: new_arc_mutex!(new_dynamic_array!(total_ais.into(), Cannon::new(), Cannon)),
-            bullets: new_arc_mutex!(new_dynamic_array!(total_ais.into(), vec![], Vec<Bullet>)),
-            enemies: new_arc_mutex!(new_dynamic_array!(total_ais.into(), vec![], Vec<Enemy>)),
-        })
+            fitness_history: new_arc_mutex!(checkpoint.fitness_history),
+            wall_time: new_arc_mutex!(
+                Duration::try_from_secs_f64(checkpoint.wall_time_secs).unwrap_or_default()
+            ),
+            episode_scores: new_arc_mutex!(new_dynamic_array!(total_ais.into(), vec![], Vec<f32>)),
+            episode_totals: new_arc_mutex!(new_dynamic_array!(
+                total_ais.into(),
+                StepInfo::default(),
+                StepInfo
+            )),
+            best_episodes: new_arc_mutex!(new_dynamic_array!(
+                total_ais.into(),
+                None,
+                Option<Replay>
+            )),
+            direction_ais: new_arc_mutex!(checkpoint.direction_ais),
+            shooting_ais: new_arc_mutex!(checkpoint.shooting_ais),
+            direction_path: Arc::new(direction_path),
+            shooting_path: Arc::new(shooting_path),
+            checkpoint_path: Arc::new(checkpoint_path),
+            network_format,
+            config,
+            seed: checkpoint.seed,
+            generation: Arc::new(AtomicUsize::new(checkpoint.generation)),
+        }
     }
     pub fn arc_clone(&self) -> Self {
         Self {
+            config: Arc::clone(&self.config),
+            seed: self.seed,
+            generation: Arc::clone(&self.generation),
             total_ais: Arc::clone(&self.total_ais),
             is_running: Arc::clone(&self.is_running),
             is_real_time: Arc::clone(&self.is_real_time),
             dimensions: Arc::clone(&self.dimensions),
-            elapsed_simulation_times: Arc::clone(&self.elapsed_simulation_times),
             selected_ai: Arc::clone(&self.selected_ai),
             ai_scores: Arc::clone(&self.ai_scores),
+            fitness_history: Arc::clone(&self.fitness_history),
+            wall_time: Arc::clone(&self.wall_time),
+            episode_scores: Arc::clone(&self.episode_scores),
+            episode_totals: Arc::clone(&self.episode_totals),
+            best_episodes: Arc::clone(&self.best_episodes),
             direction_ais: Arc::clone(&self.direction_ais),
             shooting_ais: Arc::clone(&self.shooting_ais),
-            cannons: Arc::clone(&self.cannons),
-            bullets: Arc::clone(&self.bullets),
-            enemies: Arc::clone(&self.enemies),
+            selected_world: Arc::clone(&self.selected_world),
+            worker_pool: Arc::clone(&self.worker_pool),
+            direction_path: Arc::clone(&self.direction_path),
+            shooting_path: Arc::clone(&self.shooting_path),
+            checkpoint_path: Arc::clone(&self.checkpoint_path),
+            network_format: self.network_format,
         }
     }
+    /// Resizes the arena; episodes pick up the new size when they start.
+    pub fn set_dimensions(&self, width: f32, height: f32) {
+        let mut dimensions = lock_with_error!(self.dimensions);
+        dimensions.x = width;
+        dimensions.y = height;
+    }
+    /// Writes both networks and a checkpoint to resume from. Each file is replaced atomically,
+    /// so an interrupted save leaves the previous version intact.
     pub fn save_ais(&self) -> Result<(), io::Error> {
-        let total_ais = Into::<usize>::into(*self.total_ais);
+        let checkpoint = self.checkpoint();
+
+        storage::save_networks(
+            &self.direction_path,
+            &checkpoint.direction_ais,
+            self.network_format,
+        )?;
+        storage::save_networks(
+            &self.shooting_path,
+            &checkpoint.shooting_ais,
+            self.network_format,
+        )?;
+
+        let checkpoint_json = serde_json::to_string_pretty(&checkpoint)?;
+        storage::write_atomically(&self.checkpoint_path, checkpoint_json.as_bytes())
+    }
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            config: (*self.config).clone(),
+            seed: self.seed,
+            generation: self.generation.load(Ordering::SeqCst),
+            fitness_history: lock_with_error!(self.fitness_history).clone(),
+            wall_time_secs: lock_with_error!(self.wall_time).as_secs_f64(),
+            direction_ais: lock_with_error!(self.direction_ais).clone(),
+            shooting_ais: lock_with_error!(self.shooting_ais).clone(),
+        }
+    }
+}
 
-        let direction_json =
-            serde_json::to_string_pretty(&lock_with_error!(self.direction_ais).clone()).unwrap();
-        let file_name = format!("direction_ais_{}.json", total_ais);
-        let mut file = File::create(file_name)?;
-        file.write_all(direction_json.as_bytes())?;
+impl PopulationFiles {
+    /// Files named `<name>_direction`, `<name>_shooting` and `<name>_checkpoint` in `directory`,
+    /// with the default format and mismatch policy.
+    pub fn in_directory(directory: &Path, name: &str) -> Self {
+        let format = NetworkFormat::default();
+        let path = |kind: &str, extension: &str| {
+            Some(directory.join(format!("{name}_{kind}.{extension}")))
+        };
+        Self {
+            direction_path: path("direction", format.extension()),
+            shooting_path: path("shooting", format.extension()),
+            checkpoint_path: path("checkpoint", "json"),
+            format,
+            ..Self::default()
+        }
+    }
+    /// The direction, shooting and checkpoint paths, with defaults named after `total_ais` and
+    /// the network format. Checkpoints are always JSON.
+    fn resolve(self, total_ais: NonZero<usize>) -> [PathBuf; 3] {
+        let extension = self.format.extension();
+        let default_path =
+            |name: &str, extension: &str| PathBuf::from(format!("{name}_{total_ais}.{extension}"));
+        [
+            self.direction_path
+                .unwrap_or_else(|| default_path("direction_ais", extension)),
+            self.shooting_path
+                .unwrap_or_else(|| default_path("shooting_ais", extension)),
+            self.checkpoint_path
+                .unwrap_or_else(|| default_path("checkpoint", "json")),
+        ]
+    }
+}
+fn new_random_ais(
+    config: &ExperimentConfig,
+    seed: u64,
+    total_ais: usize,
+    output_size: usize,
+    stream: RngStream,
+) -> Box<[NeuralNetwork]> {
+    (0..total_ais)
+        .map(|ai_index| {
+            let mut rng = stream_rng(seed, stream, 0, ai_index);
+            NeuralNetwork::new_random_unchecked(
+                &[
+                    config.total_view_rays,
+                    config.total_view_rays / 2,
+                    output_size,
+                ],
+                config.initializer,
+                &mut rng,
+            )
+        })
+        .collect()
+}
+type SavedAis = Option<Box<[NeuralNetwork]>>;
 
-        let shooting_json =
-            serde_json::to_string_pretty(&lock_with_error!(self.shooting_ais).clone()).unwrap();
-        let file_name = format!("shooting_ais_{}.json", total_ais);
-        let mut file = File::create(file_name)?;
-        file.write_all(shooting_json.as_bytes())?;
-        Ok(())
+/// Loads whichever of the two network files exist and checks them against `config`. The
+/// population size is `total_ais` if given, then the direction file's, then the shooting file's;
+/// saved populations of another size are an error unless `on_mismatch` adapts them.
+fn load_saved_ais(
+    config: &ExperimentConfig,
+    total_ais: Option<NonZero<usize>>,
+    direction_path: &Path,
+    shooting_path: &Path,
+    on_mismatch: MismatchPolicy,
+) -> Result<(SavedAis, SavedAis), LoadError> {
+    let direction_ais = storage::load_networks(direction_path)?;
+    let shooting_ais = storage::load_networks(shooting_path)?;
+    for (path, saved_ais, output_size) in [
+        (
+            direction_path,
+            &direction_ais,
+            NetworkPolicy::DIRECTION_OUTPUTS,
+        ),
+        (
+            shooting_path,
+            &shooting_ais,
+            NetworkPolicy::SHOOTING_OUTPUTS,
+        ),
+    ] {
+        if let Some(saved_ais) = saved_ais {
+            storage::check_networks(path, saved_ais, config.total_view_rays, output_size)?;
+        }
     }
+
+    let Some(expected) = total_ais
+        .map(usize::from)
+        .or_else(|| direction_ais.as_ref().map(|saved_ais| saved_ais.len()))
+        .or_else(|| shooting_ais.as_ref().map(|saved_ais| saved_ais.len()))
+    else {
+        return Ok((None, None));
+    };
+    let resize = |path: &Path, saved_ais: SavedAis| match saved_ais {
+        Some(saved_ais) if saved_ais.len() != expected => {
+            if on_mismatch != MismatchPolicy::Adapt {
+                return Err(LoadError::PopulationSize {
+                    path: path.to_path_buf(),
+                    expected,
+                    found: saved_ais.len(),
+                });
+            }
+            eprintln!(
+                "Adapting the {} AIs in {} to a population of {expected}",
+                saved_ais.len(),
+                path.display()
+            );
+            Ok(Some(storage::adapt_population(saved_ais, expected)))
+        }
+        saved_ais => Ok(saved_ais),
+    };
+    Ok((
+        resize(direction_path, direction_ais)?,
+        resize(shooting_path, shooting_ais)?,
+    ))
 }
@@ -0,0 +1,155 @@
+//! Draws a [`NeuralNetwork`] as a graph of nodes and edges. Edges are blue for positive weights
+//! and red for negative ones, more opaque and thicker the larger the weight is compared to the
+//! rest of its layer. Nodes are colored the same way by their value for the current input, and
+//! the largest output is circled.
+
+use na::DVector;
+use raylib::{
+    color::Color,
+    ffi::{Rectangle, Vector2},
+    prelude::{RaylibDraw, RaylibDrawHandle},
+};
+
+use crate::{environment::find_largest_index_unchecked, neural_network::NeuralNetwork};
+
+/// Outputs of the direction network, in the order of [`crate::environment::Rotation::ALL`].
+pub const DIRECTION_LABELS: [&str; 3] = ["CCW", "Hold", "CW"];
+/// Outputs of the shooting network; the first one fires.
+pub const SHOOTING_LABELS: [&str; 2] = ["Shoot", "Hold"];
+
+const POSITIVE: Color = Color {
+    r: 30,
+    g: 90,
+    b: 220,
+    a: 255,
+};
+const NEGATIVE: Color = Color {
+    r: 220,
+    g: 50,
+    b: 40,
+    a: 255,
+};
+const TITLE_HEIGHT: f32 = 24.0;
+const LABEL_WIDTH: f32 = 50.0;
+const MAX_NODE_RADIUS: f32 = 7.0;
+
+/// Draws `network` inside `area`, with node values from running it on `input`.
+pub fn draw_network(
+    d: &mut RaylibDrawHandle<'_>,
+    network: &NeuralNetwork,
+    input: &[f32],
+    title: &str,
+    output_labels: &[&str],
+    area: Rectangle,
+) {
+    d.draw_rectangle_rec(
+        area,
+        Color {
+            r: 245,
+            g: 245,
+            b: 245,
+            a: 230,
+        },
+    );
+    d.draw_rectangle_lines_ex(area, 1.0, Color::GRAY);
+    d.draw_text(
+        title,
+        (area.x + 8.0) as i32,
+        (area.y + 4.0) as i32,
+        20,
+        Color::BLACK,
+    );
+    let Ok(values) = network.layer_outputs(&DVector::from_column_slice(input)) else {
+        return;
+    };
+    let positions = node_positions(&values, area);
+    let radius = positions
+        .iter()
+        .map(|layer| node_spacing(layer.len(), area) / 2.5)
+        .fold(MAX_NODE_RADIUS, f32::min);
+
+    for (layer, weight) in network.weights().iter().enumerate() {
+        let largest = weight.amax().max(f32::EPSILON);
+        for row in 0..weight.nrows() {
+            for column in 0..weight.ncols() {
+                let value = weight[(row, column)];
+                let strength = value.abs() / largest;
+                let color = if value < 0.0 { NEGATIVE } else { POSITIVE };
+                d.draw_line_ex(
+                    positions[layer][column],
+                    positions[layer + 1][row],
+                    0.5 + 1.5 * strength,
+                    color.fade(0.08 + 0.72 * strength),
+                );
+            }
+        }
+    }
+
+    let output_layer = values.len() - 1;
+    let chosen = find_largest_index_unchecked(values[output_layer].as_slice());
+    for (layer, (layer_values, layer_positions)) in values.iter().zip(positions.iter()).enumerate()
+    {
+        for (node, (value, position)) in layer_values.iter().zip(layer_positions).enumerate() {
+            d.draw_circle_v(*position, radius, node_color(*value));
+            d.draw_circle_lines(
+                position.x as i32,
+                position.y as i32,
+                radius,
+                Color::DARKGRAY,
+            );
+            if layer != output_layer {
+                continue;
+            }
+            if node == chosen {
+                d.draw_circle_lines(
+                    position.x as i32,
+                    position.y as i32,
+                    radius + 3.0,
+                    Color::BLACK,
+                );
+            }
+            let label = output_labels.get(node).copied().unwrap_or("");
+            d.draw_text(
+                &format!("{label} {value:.2}"),
+                (position.x + radius + 6.0) as i32,
+                (position.y - 5.0) as i32,
+                10,
+                Color::BLACK,
+            );
+        }
+    }
+}
+/// Centers of every node, one column per layer.
+fn node_positions(values: &[DVector<f32>], area: Rectangle) -> Vec<Vec<Vector2>> {
+    let left = area.x + 2.0 * MAX_NODE_RADIUS;
+    let width = area.width - LABEL_WIDTH - 4.0 * MAX_NODE_RADIUS;
+    let column_spacing = width / (values.len() - 1).max(1) as f32;
+    values
+        .iter()
+        .enumerate()
+        .map(|(layer, layer_values)| {
+            let spacing = node_spacing(layer_values.len(), area);
+            (0..layer_values.len())
+                .map(|node| Vector2 {
+                    x: left + layer as f32 * column_spacing,
+                    y: area.y + TITLE_HEIGHT + (node as f32 + 0.5) * spacing,
+                })
+                .collect()
+        })
+        .collect()
+}
+fn node_spacing(total_nodes: usize, area: Rectangle) -> f32 {
+    (area.height - TITLE_HEIGHT) / total_nodes.max(1) as f32
+}
+/// White for 0, shading into blue for positive and red for negative values up to ±1.
+fn node_color(value: f32) -> Color {
+    let target = if value < 0.0 { NEGATIVE } else { POSITIVE };
+    let amount = value.abs().min(1.0);
+    let blend = |channel: u8| (255.0 + (f32::from(channel) - 255.0) * amount) as u8;
+    Color {
+        r: blend(target.r),
+        g: blend(target.g),
+        b: blend(target.b),
+        a: 255,
+    }
+}
@@ -1,81 +1,471 @@
-#![allow(dead_code)]
-
-use std::f32::consts::E;
+use std::num::NonZero;
 
 use na::{self, DMatrix, DVector};
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 use typed_floats::Positive;
 
+use crate::{initializer::Initializer, new_dynamic_array, optimizer::Optimizer};
+
+pub const LEAKY_RELU_SLOPE: f32 = 0.01;
+
+/// The function applied to the weighted sums of one layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[default]
+    Tanh,
+    Sigmoid,
+    Relu,
+    /// ReLU with a slope of 0.01 for negative inputs.
+    LeakyRelu,
+    Linear,
+    /// Turns the layer into a probability distribution; only allowed on the output layer.
+    Softmax,
+}
+
+impl Activation {
+    pub fn apply(self, values: &mut DVector<f32>) {
+        match self {
+            Activation::Tanh => values.apply(|value| *value = value.tanh()),
+            Activation::Sigmoid => values.apply(|value| *value = 1.0 / (1.0 + (-*value).exp())),
+            Activation::Relu => values.apply(|value| *value = value.max(0.0)),
+            Activation::LeakyRelu => values.apply(|value| {
+                if *value < 0.0 {
+                    *value *= LEAKY_RELU_SLOPE;
+                }
+            }),
+            Activation::Linear => {}
+            Activation::Softmax => *values = softmax(values),
+        }
+    }
+    /// Turns the gradient with respect to the activated values into the gradient with respect
+    /// to the weighted sums, using only the activated values.
+    fn backpropagate(self, activated: &DVector<f32>, gradient: &mut DVector<f32>) {
+        match self {
+            Activation::Tanh => gradient.zip_apply(activated, |gradient, activated| {
+                *gradient *= 1.0 - activated * activated
+            }),
+            Activation::Sigmoid => gradient.zip_apply(activated, |gradient, activated| {
+                *gradient *= activated * (1.0 - activated)
+            }),
+            Activation::Relu => gradient.zip_apply(activated, |gradient, activated| {
+                if activated <= 0.0 {
+                    *gradient = 0.0;
+                }
+            }),
+            Activation::LeakyRelu => gradient.zip_apply(activated, |gradient, activated| {
+                if activated < 0.0 {
+                    *gradient *= LEAKY_RELU_SLOPE;
+                }
+            }),
+            Activation::Linear => {}
+            Activation::Softmax => {
+                let weighted_sum = gradient.dot(activated);
+                gradient.zip_apply(activated, |gradient, activated| {
+                    *gradient = activated * (*gradient - weighted_sum)
+                });
+            }
+        }
+    }
+}
+
+/// How [`NeuralNetwork::crossover`] combines the parameters of two parents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Crossover {
+    /// Every parameter comes from either parent with equal probability.
+    Uniform,
+    /// The flattened genome is cut once; the child takes the first part from one parent and the
+    /// rest from the other.
+    SinglePoint,
+    /// Like single point, but switching parents at `points` distinct cuts.
+    MultiPoint { points: usize },
+    /// Every layer's weights and biases come together from either parent.
+    LayerWise,
+}
+
+/// The quantity gradient-based training minimizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Loss {
+    /// Mean of the squared differences between output and target.
+    MeanSquaredError,
+    /// Cross-entropy between a one-hot or probability target and the softmax of the output, so
+    /// the output layer should be linear rather than [`Activation::Softmax`].
+    SoftmaxCrossEntropy,
+}
+
+impl Loss {
+    /// Returns the loss and its gradient with respect to `output`.
+    pub fn evaluate(self, output: &DVector<f32>, target: &DVector<f32>) -> (f32, DVector<f32>) {
+        match self {
+            Loss::MeanSquaredError => {
+                let difference = output - target;
+                let total_outputs = output.len() as f32;
+                (
+                    difference.norm_squared() / total_outputs,
+                    difference * (2.0 / total_outputs),
+                )
+            }
+            Loss::SoftmaxCrossEntropy => {
+                let probabilities = softmax(output);
+                let loss = -target
+                    .iter()
+                    .zip(probabilities.iter())
+                    .map(|(target, probability)| target * probability.max(f32::MIN_POSITIVE).ln())
+                    .sum::<f32>();
+                (loss, probabilities - target)
+            }
+        }
+    }
+}
+
+/// Loss gradients for every weight and bias of a [`NeuralNetwork`], laid out like its layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradients {
+    pub weights: Box<[DMatrix<f32>]>,
+    pub biases: Box<[DVector<f32>]>,
+}
+
+impl Gradients {
+    fn zeros_like(network: &NeuralNetwork) -> Self {
+        Self {
+            weights: network
+                .weights
+                .iter()
+                .map(|weight| DMatrix::zeros(weight.nrows(), weight.ncols()))
+                .collect(),
+            biases: network
+                .biases
+                .iter()
+                .map(|bias| DVector::zeros(bias.len()))
+                .collect(),
+        }
+    }
+    /// Every gradient tensor as a flat slice, in the order of [`NeuralNetwork::parameters_mut`].
+    pub fn as_slices(&self) -> Box<[&[f32]]> {
+        self.weights
+            .iter()
+            .map(|weight| weight.as_slice())
+            .chain(self.biases.iter().map(|bias| bias.as_slice()))
+            .collect()
+    }
+}
+
+/// A fully connected feed-forward network with its own activation after every layer.
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedNetwork")]
 pub struct NeuralNetwork {
     input_size: usize,
     output_size: usize,
     weights: Box<[DMatrix<f32>]>,
     biases: Box<[DVector<f32>]>,
+    activations: Box<[Activation]>,
+}
+
+/// The stored form of a [`NeuralNetwork`]; files written before activations were configurable
+/// have no `activations` and load as all-tanh.
+#[derive(Deserialize)]
+struct SerializedNetwork {
+    input_size: usize,
+    output_size: usize,
+    weights: Box<[DMatrix<f32>]>,
+    biases: Box<[DVector<f32>]>,
+    activations: Option<Box<[Activation]>>,
+}
+
+impl TryFrom<SerializedNetwork> for NeuralNetwork {
+    type Error = String;
+
+    fn try_from(network: SerializedNetwork) -> Result<Self, Self::Error> {
+        if network.weights.len() != network.biases.len() {
+            return Err(format!(
+                "Neural network has {} weight matrices but {} bias vectors",
+                network.weights.len(),
+                network.biases.len()
+            ));
+        }
+        if network.weights.is_empty() {
+            return Err("Neural network has no layers".to_string());
+        }
+        let mut layer_input_size = network.input_size;
+        for (layer, (weight, bias)) in network
+            .weights
+            .iter()
+            .zip(network.biases.iter())
+            .enumerate()
+        {
+            if weight.ncols() != layer_input_size || bias.nrows() != weight.nrows() {
+                return Err(format!(
+                    "Layer {layer} has {}x{} weights and {} biases but takes {layer_input_size} inputs",
+                    weight.nrows(),
+                    weight.ncols(),
+                    bias.nrows()
+                ));
+            }
+            layer_input_size = weight.nrows();
+        }
+        if layer_input_size != network.output_size {
+            return Err(format!(
+                "Neural network claims {} outputs but its last layer has {layer_input_size}",
+                network.output_size
+            ));
+        }
+        let activations = network.activations.unwrap_or_else(|| {
+            new_dynamic_array!(network.weights.len(), Activation::Tanh, Activation)
+        });
+        Self {
+            input_size: network.input_size,
+            output_size: network.output_size,
+            weights: network.weights,
+            biases: network.biases,
+            activations: Box::new([]),
+        }
+        .with_activations(&activations)
+    }
 }
 
 impl NeuralNetwork {
-    pub fn new_random_unchecked(layer_sizes: &[usize]) -> Self {
+    /// Builds a network with one layer per entry of `layer_sizes`, without checking the sizes.
+    pub fn new_random_unchecked(
+        layer_sizes: &[usize],
+        initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Self {
         let total_layers = layer_sizes.len();
         Self {
             input_size: layer_sizes[0],
             output_size: layer_sizes[total_layers - 1],
             weights: (1..total_layers)
-                .map(|i| DMatrix::new_random(layer_sizes[i], layer_sizes[i - 1]))
+                .map(|i| {
+                    DMatrix::from_fn(layer_sizes[i], layer_sizes[i - 1], |_, _| {
+                        initializer.weight(layer_sizes[i - 1], layer_sizes[i], rng)
+                    })
+                })
                 .collect::<Vec<DMatrix<f32>>>()
                 .into_boxed_slice(),
             biases: (1..total_layers)
-                .map(|i| DVector::new_random(layer_sizes[i]))
+                .map(|i| DVector::from_fn(layer_sizes[i], |_, _| initializer.bias(rng)))
                 .collect::<Vec<DVector<f32>>>()
                 .into_boxed_slice(),
+            activations: new_dynamic_array!(total_layers - 1, Activation::Tanh, Activation),
         }
     }
-    pub fn new_random(layer_sizes: &[usize]) -> Result<Self, String> {
+    /// Builds a network drawn from `initializer`, requiring at least one hidden layer.
+    pub fn new_random(
+        layer_sizes: &[usize],
+        initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Result<Self, String> {
         let total_layers = layer_sizes.len();
         if total_layers <= 2 {
             return Err(
                 "Neural network must have at least 2 layers for input and output.".to_string(),
             );
         }
-        Ok(Self::new_random_unchecked(layer_sizes))
-    }
-    pub fn run_unchecked(&self, input: &DVector<f32>) -> DVector<f32> {
-        let mut current_value = input.clone();
-        for (weight, bias) in self.weights.iter().zip(self.biases.iter()) {
-            current_value = weight * current_value + bias;
-            current_value.apply(|value| *value = NeuralNetwork::activation_function(*value));
+        if layer_sizes.contains(&0) {
+            return Err("Neural network layers must not be empty".to_string());
         }
-        current_value
+        initializer.validate()?;
+        Ok(Self::new_random_unchecked(layer_sizes, initializer, rng))
     }
-    pub fn run(&self, input: &DVector<f32>) -> Result<DVector<f32>, String> {
-        if input.nrows() != self.input_size {
+    /// Replaces the activation of every layer, with one entry per layer after the input.
+    pub fn with_activations(mut self, activations: &[Activation]) -> Result<Self, String> {
+        if activations.len() != self.weights.len() {
             return Err(format!(
-                "Incorrect input size for neural network. Expected {}",
-                self.input_size
+                "Expected {} activations, one per layer after the input, got {}",
+                self.weights.len(),
+                activations.len()
             ));
         }
+        if activations
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|activation| *activation == Activation::Softmax)
+        {
+            return Err("Softmax is only allowed on the output layer".to_string());
+        }
+        self.activations = activations.into();
+        Ok(self)
+    }
+    pub fn activations(&self) -> &[Activation] {
+        &self.activations
+    }
+    /// Builds a network from stored layers, checking that they chain from `input_size` to
+    /// `output_size`.
+    pub fn from_parts(
+        input_size: usize,
+        output_size: usize,
+        weights: Box<[DMatrix<f32>]>,
+        biases: Box<[DVector<f32>]>,
+        activations: Box<[Activation]>,
+    ) -> Result<Self, String> {
+        SerializedNetwork {
+            input_size,
+            output_size,
+            weights,
+            biases,
+            activations: Some(activations),
+        }
+        .try_into()
+    }
+    pub fn weights(&self) -> &[DMatrix<f32>] {
+        &self.weights
+    }
+    pub fn biases(&self) -> &[DVector<f32>] {
+        &self.biases
+    }
+    /// Runs a forward pass, panicking if `input` has the wrong length.
+    pub fn run_unchecked(&self, input: &DVector<f32>) -> DVector<f32> {
+        self.forward(input)
+            .pop()
+            .expect("A forward pass keeps at least the input")
+    }
+    /// Runs a forward pass after checking the length of `input`.
+    pub fn run(&self, input: &DVector<f32>) -> Result<DVector<f32>, String> {
+        self.check_input(input)?;
         Ok(self.run_unchecked(input))
     }
-    pub fn tweak_continuous(&mut self, change: Positive<f32>) {
-        let mut rng = rand::thread_rng();
+    /// Runs a forward pass and keeps every layer's values: `input` first, then the activated
+    /// output of each layer.
+    pub fn layer_outputs(&self, input: &DVector<f32>) -> Result<Box<[DVector<f32>]>, String> {
+        self.check_input(input)?;
+        Ok(self.forward(input).into_boxed_slice())
+    }
+    /// Adds uniform noise in `(-change, change)` to every parameter, clamped to `[-1, 1]`.
+    pub fn tweak_continuous(&mut self, change: Positive<f32>, rng: &mut impl Rng) {
         let change_float: f32 = change.into();
 
         for weight in self.weights.iter_mut() {
             weight.apply(|element| {
-                *element += rng.gen_range(-change_float..change_float) as f32;
+                *element += rng.gen_range(-change_float..change_float);
                 *element = element.clamp(-1.0, 1.0);
             });
         }
         for bias in self.biases.iter_mut() {
             bias.apply(|element| {
-                *element += rng.gen_range(-change_float..change_float) as f32;
+                *element += rng.gen_range(-change_float..change_float);
                 *element = element.clamp(-1.0, 1.0);
             });
         }
     }
-    pub fn tweak_discrete(&mut self, change: u32) {
-        let mut rng = rand::thread_rng();
+    /// Combines this network with `other` into a child that keeps this network's activations,
+    /// failing if the two do not have the same layer sizes.
+    pub fn crossover(
+        &self,
+        other: &NeuralNetwork,
+        crossover: Crossover,
+        rng: &mut impl Rng,
+    ) -> Result<NeuralNetwork, String> {
+        self.check_compatible(other)?;
+        let mut child = self.clone();
+        let mut other = other.clone();
+        match crossover {
+            Crossover::Uniform => {
+                for (child_tensor, other_tensor) in child
+                    .parameters_mut()
+                    .iter_mut()
+                    .zip(other.parameters_mut().iter())
+                {
+                    for (child_value, other_value) in
+                        child_tensor.iter_mut().zip(other_tensor.iter())
+                    {
+                        if rng.gen_bool(0.5) {
+                            *child_value = *other_value;
+                        }
+                    }
+                }
+            }
+            Crossover::SinglePoint => child.cross_at_points(&mut other, 1, rng)?,
+            Crossover::MultiPoint { points } => child.cross_at_points(&mut other, points, rng)?,
+            Crossover::LayerWise => {
+                for layer in 0..child.weights.len() {
+                    if rng.gen_bool(0.5) {
+                        child.weights[layer] = other.weights[layer].clone();
+                        child.biases[layer] = other.biases[layer].clone();
+                    }
+                }
+            }
+        }
+        Ok(child)
+    }
+    /// Checks that `other` has the same layer sizes, so the two can be crossed over.
+    pub fn check_compatible(&self, other: &NeuralNetwork) -> Result<(), String> {
+        if self.weights.len() != other.weights.len()
+            || self
+                .weights
+                .iter()
+                .zip(other.weights.iter())
+                .any(|(weight, other_weight)| weight.shape() != other_weight.shape())
+        {
+            return Err(format!(
+                "Cannot cross over networks with layer sizes {:?} and {:?}",
+                self.layer_sizes(),
+                other.layer_sizes()
+            ));
+        }
+        Ok(())
+    }
+    pub fn layer_sizes(&self) -> Box<[usize]> {
+        std::iter::once(self.input_size)
+            .chain(self.weights.iter().map(|weight| weight.nrows()))
+            .collect()
+    }
+    pub fn parameter_count(&self) -> usize {
+        self.weights
+            .iter()
+            .map(|weight| weight.len())
+            .sum::<usize>()
+            + self.biases.iter().map(|bias| bias.len()).sum::<usize>()
+    }
+    fn cross_at_points(
+        &mut self,
+        other: &mut NeuralNetwork,
+        points: usize,
+        rng: &mut impl Rng,
+    ) -> Result<(), String> {
+        let genome_length = self.parameter_count();
+        if genome_length < 2 {
+            return Err(format!(
+                "Cannot cross over networks with {genome_length} parameters"
+            ));
+        }
+        if points == 0 || points >= genome_length {
+            return Err(format!(
+                "Crossover needs between 1 and {} points, got {points}",
+                genome_length - 1
+            ));
+        }
+        let mut cuts = rand::seq::index::sample(rng, genome_length - 1, points)
+            .into_iter()
+            .map(|cut| cut + 1)
+            .collect::<Vec<usize>>();
+        cuts.sort_unstable();
+
+        let mut genome_index = 0;
+        let mut next_cut = 0;
+        let mut from_other = false;
+        for (tensor, other_tensor) in self
+            .parameters_mut()
+            .iter_mut()
+            .zip(other.parameters_mut().iter())
+        {
+            for (value, other_value) in tensor.iter_mut().zip(other_tensor.iter()) {
+                while next_cut < cuts.len() && cuts[next_cut] == genome_index {
+                    from_other = !from_other;
+                    next_cut += 1;
+                }
+                if from_other {
+                    *value = *other_value;
+                }
+                genome_index += 1;
+            }
+        }
+        Ok(())
+    }
+    /// Adds a random integer in `[-change, change)` to every parameter, clamped to `[-1, 1]`.
+    pub fn tweak_discrete(&mut self, change: u32, rng: &mut impl Rng) {
         let change_int = change as i32;
 
         for weight in self.weights.iter_mut() {
@@ -91,7 +481,165 @@ impl NeuralNetwork {
             });
         }
     }
-    fn activation_function(value: f32) -> f32 {
-        (E.powf(value)-E.powf(-value))/(E.powf(value)+E.powf(-value))
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+    pub fn output_size(&self) -> usize {
+        self.output_size
+    }
+    /// Every weight matrix followed by every bias vector as flat, mutable slices.
+    pub fn parameters_mut(&mut self) -> Box<[&mut [f32]]> {
+        self.weights
+            .iter_mut()
+            .map(|weight| weight.as_mut_slice())
+            .chain(self.biases.iter_mut().map(|bias| bias.as_mut_slice()))
+            .collect()
+    }
+    /// Computes the loss of a single sample together with the gradient of every parameter.
+    pub fn backward(
+        &self,
+        input: &DVector<f32>,
+        target: &DVector<f32>,
+        loss: Loss,
+    ) -> Result<(f32, Gradients), String> {
+        self.check_sample(input, target)?;
+
+        let activations = self.forward(input);
+        let (loss_value, output_gradient) =
+            loss.evaluate(&activations[activations.len() - 1], target);
+        let mut gradients = Gradients::zeros_like(self);
+        let mut delta = output_gradient;
+        for layer in (0..self.weights.len()).rev() {
+            self.activations[layer].backpropagate(&activations[layer + 1], &mut delta);
+            gradients.weights[layer] = &delta * activations[layer].transpose();
+            let previous_delta = self.weights[layer].tr_mul(&delta);
+            gradients.biases[layer] = delta;
+            delta = previous_delta;
+        }
+        Ok((loss_value, gradients))
+    }
+    /// Mean loss of the samples without computing gradients.
+    pub fn loss(
+        &self,
+        samples: &[(DVector<f32>, DVector<f32>)],
+        loss: Loss,
+    ) -> Result<f32, String> {
+        if samples.is_empty() {
+            return Err("Cannot compute the loss of an empty set of samples".to_string());
+        }
+        let mut total_loss = 0.0;
+        for (input, target) in samples {
+            self.check_sample(input, target)?;
+            total_loss += loss.evaluate(&self.run_unchecked(input), target).0;
+        }
+        Ok(total_loss / samples.len() as f32)
+    }
+    /// Takes one optimizer step on the mean gradient of `batch` and returns its mean loss.
+    pub fn train_batch(
+        &mut self,
+        batch: &[(DVector<f32>, DVector<f32>)],
+        loss: Loss,
+        optimizer: &mut impl Optimizer,
+    ) -> Result<f32, String> {
+        if batch.is_empty() {
+            return Err("Cannot train on an empty batch".to_string());
+        }
+        let mut total_loss = 0.0;
+        let mut total_gradients = Gradients::zeros_like(self);
+        for (input, target) in batch {
+            let (sample_loss, gradients) = self.backward(input, target, loss)?;
+            total_loss += sample_loss;
+            for (total, gradient) in total_gradients
+                .weights
+                .iter_mut()
+                .zip(gradients.weights.iter())
+            {
+                *total += gradient;
+            }
+            for (total, gradient) in total_gradients
+                .biases
+                .iter_mut()
+                .zip(gradients.biases.iter())
+            {
+                *total += gradient;
+            }
+        }
+        let scale = 1.0 / batch.len() as f32;
+        for gradient in total_gradients.weights.iter_mut() {
+            *gradient *= scale;
+        }
+        for gradient in total_gradients.biases.iter_mut() {
+            *gradient *= scale;
+        }
+
+        optimizer.step(&mut self.parameters_mut(), &total_gradients.as_slices());
+        Ok(total_loss * scale)
+    }
+    /// Shuffles `samples` with `rng`, trains on every mini-batch once and returns the mean loss.
+    pub fn train_epoch(
+        &mut self,
+        samples: &[(DVector<f32>, DVector<f32>)],
+        batch_size: NonZero<usize>,
+        loss: Loss,
+        optimizer: &mut impl Optimizer,
+        rng: &mut impl Rng,
+    ) -> Result<f32, String> {
+        if samples.is_empty() {
+            return Err("Cannot train on an empty set of samples".to_string());
+        }
+        let mut order = (0..samples.len()).collect::<Vec<usize>>();
+        order.shuffle(rng);
+
+        let mut total_loss = 0.0;
+        for batch_indices in order.chunks(batch_size.into()) {
+            let batch = batch_indices
+                .iter()
+                .map(|&index| samples[index].clone())
+                .collect::<Vec<_>>();
+            total_loss += self.train_batch(&batch, loss, optimizer)? * batch.len() as f32;
+        }
+        Ok(total_loss / samples.len() as f32)
+    }
+    /// The forward pass behind [`Self::run`], [`Self::layer_outputs`] and [`Self::backward`]:
+    /// `input` followed by the activated output of every layer.
+    fn forward(&self, input: &DVector<f32>) -> Vec<DVector<f32>> {
+        let mut outputs = Vec::with_capacity(self.weights.len() + 1);
+        outputs.push(input.clone());
+        for ((weight, bias), activation) in self
+            .weights
+            .iter()
+            .zip(self.biases.iter())
+            .zip(self.activations.iter())
+        {
+            let mut current_value = weight * &outputs[outputs.len() - 1] + bias;
+            activation.apply(&mut current_value);
+            outputs.push(current_value);
+        }
+        outputs
+    }
+    fn check_input(&self, input: &DVector<f32>) -> Result<(), String> {
+        if input.nrows() != self.input_size {
+            return Err(format!(
+                "Incorrect input size for neural network. Expected {}",
+                self.input_size
+            ));
+        }
+        Ok(())
+    }
+    fn check_sample(&self, input: &DVector<f32>, target: &DVector<f32>) -> Result<(), String> {
+        self.check_input(input)?;
+        if target.nrows() != self.output_size {
+            return Err(format!(
+                "Incorrect target size for neural network. Expected {}",
+                self.output_size
+            ));
+        }
+        Ok(())
     }
 }
+fn softmax(values: &DVector<f32>) -> DVector<f32> {
+    let max = values.max();
+    let exponentials = values.map(|value| (value - max).exp());
+    let total = exponentials.sum();
+    exponentials / total
+}
@@ -0,0 +1,287 @@
+//! Exports a [`NeuralNetwork`] as an ONNX model, so that trained policies can run in any ONNX
+//! runtime.
+//!
+//! Every layer becomes a `Gemm` node computing `input * weights^T + biases`, followed by a node
+//! for its activation. The input has shape `[batch, input_size]` and the output
+//! `[batch, output_size]`.
+//!
+//! The protobuf messages below are the subset of `onnx.proto` the exporter needs, with the
+//! field numbers of the official schema.
+
+use std::{fs, io, path::Path};
+
+use prost::Message;
+
+use crate::neural_network::{Activation, NeuralNetwork, LEAKY_RELU_SLOPE};
+
+/// Version of the ONNX file format written.
+pub const IR_VERSION: i64 = 8;
+/// Version of the default operator set the graph uses.
+pub const OPSET_VERSION: i64 = 13;
+/// `TensorProto.DataType.FLOAT`.
+pub const FLOAT_TYPE: i32 = 1;
+/// `AttributeProto.AttributeType.FLOAT`.
+pub const FLOAT_ATTRIBUTE: i32 = 1;
+/// `AttributeProto.AttributeType.INT`.
+pub const INT_ATTRIBUTE: i32 = 2;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ModelProto {
+    #[prost(int64, tag = "1")]
+    pub ir_version: i64,
+    #[prost(string, tag = "2")]
+    pub producer_name: String,
+    #[prost(string, tag = "3")]
+    pub producer_version: String,
+    #[prost(string, tag = "6")]
+    pub doc_string: String,
+    #[prost(message, optional, tag = "7")]
+    pub graph: Option<GraphProto>,
+    #[prost(message, repeated, tag = "8")]
+    pub opset_import: Vec<OperatorSetIdProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct OperatorSetIdProto {
+    #[prost(string, tag = "1")]
+    pub domain: String,
+    #[prost(int64, tag = "2")]
+    pub version: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GraphProto {
+    #[prost(message, repeated, tag = "1")]
+    pub node: Vec<NodeProto>,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, repeated, tag = "5")]
+    pub initializer: Vec<TensorProto>,
+    #[prost(string, tag = "10")]
+    pub doc_string: String,
+    #[prost(message, repeated, tag = "11")]
+    pub input: Vec<ValueInfoProto>,
+    #[prost(message, repeated, tag = "12")]
+    pub output: Vec<ValueInfoProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct NodeProto {
+    #[prost(string, repeated, tag = "1")]
+    pub input: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub output: Vec<String>,
+    #[prost(string, tag = "3")]
+    pub name: String,
+    #[prost(string, tag = "4")]
+    pub op_type: String,
+    #[prost(message, repeated, tag = "5")]
+    pub attribute: Vec<AttributeProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AttributeProto {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(float, tag = "2")]
+    pub f: f32,
+    #[prost(int64, tag = "3")]
+    pub i: i64,
+    #[prost(int32, tag = "20")]
+    pub r#type: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorProto {
+    #[prost(int64, repeated, tag = "1")]
+    pub dims: Vec<i64>,
+    #[prost(int32, tag = "2")]
+    pub data_type: i32,
+    /// Row-major values.
+    #[prost(float, repeated, tag = "4")]
+    pub float_data: Vec<f32>,
+    #[prost(string, tag = "8")]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ValueInfoProto {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, optional, tag = "2")]
+    pub r#type: Option<TypeProto>,
+    #[prost(string, tag = "3")]
+    pub doc_string: String,
+}
+
+/// `TypeProto` with only the `tensor_type` case of its `value` oneof.
+#[derive(Clone, PartialEq, Message)]
+pub struct TypeProto {
+    #[prost(message, optional, tag = "1")]
+    pub tensor_type: Option<TensorTypeProto>,
+}
+
+/// `TypeProto.Tensor`.
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorTypeProto {
+    #[prost(int32, tag = "1")]
+    pub elem_type: i32,
+    #[prost(message, optional, tag = "2")]
+    pub shape: Option<TensorShapeProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorShapeProto {
+    #[prost(message, repeated, tag = "1")]
+    pub dim: Vec<Dimension>,
+}
+
+/// `TensorShapeProto.Dimension`; exactly one of the two fields is set.
+#[derive(Clone, PartialEq, Message)]
+pub struct Dimension {
+    #[prost(int64, optional, tag = "1")]
+    pub dim_value: Option<i64>,
+    #[prost(string, optional, tag = "2")]
+    pub dim_param: Option<String>,
+}
+
+/// Names given to the exported graph and its tensors.
+pub struct OnnxNames<'a> {
+    pub graph: &'a str,
+    pub input: &'a str,
+    pub output: &'a str,
+    /// Describes the meaning of the output for whoever runs the model.
+    pub doc_string: &'a str,
+}
+
+/// Builds the ONNX model of `network`.
+pub fn model(network: &NeuralNetwork, names: &OnnxNames) -> ModelProto {
+    let mut nodes = vec![];
+    let mut initializers = vec![];
+    let mut current = names.input.to_string();
+    let total_layers = network.weights().len();
+    for (layer, ((weight, bias), activation)) in network
+        .weights()
+        .iter()
+        .zip(network.biases())
+        .zip(network.activations())
+        .enumerate()
+    {
+        let is_last = layer + 1 == total_layers;
+        let weights_name = format!("layer{layer}.weights");
+        let biases_name = format!("layer{layer}.biases");
+        initializers.push(TensorProto {
+            dims: vec![weight.nrows() as i64, weight.ncols() as i64],
+            data_type: FLOAT_TYPE,
+            float_data: weight.transpose().iter().copied().collect(),
+            name: weights_name.clone(),
+        });
+        initializers.push(TensorProto {
+            dims: vec![bias.nrows() as i64],
+            data_type: FLOAT_TYPE,
+            float_data: bias.iter().copied().collect(),
+            name: biases_name.clone(),
+        });
+
+        let weighted_sum = if is_last && *activation == Activation::Linear {
+            names.output.to_string()
+        } else {
+            format!("layer{layer}.weighted_sum")
+        };
+        nodes.push(NodeProto {
+            input: vec![current, weights_name, biases_name],
+            output: vec![weighted_sum.clone()],
+            name: format!("layer{layer}.gemm"),
+            op_type: "Gemm".to_string(),
+            attribute: vec![int_attribute("transB", 1)],
+        });
+        current = weighted_sum;
+
+        let (op_type, attribute) = match activation {
+            Activation::Linear => continue,
+            Activation::Tanh => ("Tanh", vec![]),
+            Activation::Sigmoid => ("Sigmoid", vec![]),
+            Activation::Relu => ("Relu", vec![]),
+            Activation::LeakyRelu => (
+                "LeakyRelu",
+                vec![float_attribute("alpha", LEAKY_RELU_SLOPE)],
+            ),
+            Activation::Softmax => ("Softmax", vec![int_attribute("axis", -1)]),
+        };
+        let activated = if is_last {
+            names.output.to_string()
+        } else {
+            format!("layer{layer}.activated")
+        };
+        nodes.push(NodeProto {
+            input: vec![current],
+            output: vec![activated.clone()],
+            name: format!("layer{layer}.{}", op_type.to_lowercase()),
+            op_type: op_type.to_string(),
+            attribute,
+        });
+        current = activated;
+    }
+
+    ModelProto {
+        ir_version: IR_VERSION,
+        producer_name: env!("CARGO_PKG_NAME").to_string(),
+        producer_version: env!("CARGO_PKG_VERSION").to_string(),
+        doc_string: names.doc_string.to_string(),
+        graph: Some(GraphProto {
+            node: nodes,
+            name: names.graph.to_string(),
+            initializer: initializers,
+            doc_string: names.doc_string.to_string(),
+            input: vec![tensor_info(names.input, network.input_size())],
+            output: vec![tensor_info(names.output, network.output_size())],
+        }),
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: OPSET_VERSION,
+        }],
+    }
+}
+/// Writes the ONNX model of `network` to `path`.
+pub fn export(network: &NeuralNetwork, names: &OnnxNames, path: &Path) -> Result<(), io::Error> {
+    fs::write(path, model(network, names).encode_to_vec())
+}
+fn tensor_info(name: &str, size: usize) -> ValueInfoProto {
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            tensor_type: Some(TensorTypeProto {
+                elem_type: FLOAT_TYPE,
+                shape: Some(TensorShapeProto {
+                    dim: vec![
+                        Dimension {
+                            dim_value: None,
+                            dim_param: Some("batch".to_string()),
+                        },
+                        Dimension {
+                            dim_value: Some(size as i64),
+                            dim_param: None,
+                        },
+                    ],
+                }),
+            }),
+        }),
+        doc_string: String::new(),
+    }
+}
+fn int_attribute(name: &str, value: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        i: value,
+        r#type: INT_ATTRIBUTE,
+        ..AttributeProto::default()
+    }
+}
+fn float_attribute(name: &str, value: f32) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        f: value,
+        r#type: FLOAT_ATTRIBUTE,
+        ..AttributeProto::default()
+    }
+}
@@ -0,0 +1,117 @@
+//! Gradient-descent optimizers used by [`crate::neural_network::NeuralNetwork::train_batch`].
+
+/// Updates parameters from their gradients. Both slices list the same tensors in the same order
+/// on every call, so optimizers may keep per-parameter state between steps.
+pub trait Optimizer {
+    fn step(&mut self, parameters: &mut [&mut [f32]], gradients: &[&[f32]]);
+}
+
+/// Stochastic gradient descent with classical momentum; a momentum of 0 gives plain SGD.
+#[derive(Clone, Debug)]
+pub struct Sgd {
+    pub learning_rate: f32,
+    pub momentum: f32,
+    velocities: Vec<Vec<f32>>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f32, momentum: f32) -> Self {
+        Self {
+            learning_rate,
+            momentum,
+            velocities: vec![],
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, parameters: &mut [&mut [f32]], gradients: &[&[f32]]) {
+        resize_state(&mut self.velocities, gradients);
+        for ((parameter, gradient), velocity) in parameters
+            .iter_mut()
+            .zip(gradients.iter())
+            .zip(self.velocities.iter_mut())
+        {
+            for ((value, gradient), velocity) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(velocity.iter_mut())
+            {
+                *velocity = self.momentum * *velocity - self.learning_rate * gradient;
+                *value += *velocity;
+            }
+        }
+    }
+}
+
+/// Adam with bias-corrected first and second moment estimates.
+#[derive(Clone, Debug)]
+pub struct Adam {
+    pub learning_rate: f32,
+    pub beta1: f32,
+    pub beta2: f32,
+    pub epsilon: f32,
+    time_step: i32,
+    first_moments: Vec<Vec<f32>>,
+    second_moments: Vec<Vec<f32>>,
+}
+
+impl Adam {
+    /// Uses the customary defaults of `beta1 = 0.9`, `beta2 = 0.999` and `epsilon = 1e-8`.
+    pub fn new(learning_rate: f32) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            time_step: 0,
+            first_moments: vec![],
+            second_moments: vec![],
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, parameters: &mut [&mut [f32]], gradients: &[&[f32]]) {
+        resize_state(&mut self.first_moments, gradients);
+        resize_state(&mut self.second_moments, gradients);
+        self.time_step = self.time_step.saturating_add(1);
+        let first_correction = 1.0 - self.beta1.powi(self.time_step);
+        let second_correction = 1.0 - self.beta2.powi(self.time_step);
+
+        for (((parameter, gradient), first_moment), second_moment) in parameters
+            .iter_mut()
+            .zip(gradients.iter())
+            .zip(self.first_moments.iter_mut())
+            .zip(self.second_moments.iter_mut())
+        {
+            for (((value, gradient), first), second) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(first_moment.iter_mut())
+                .zip(second_moment.iter_mut())
+            {
+                *first = self.beta1 * *first + (1.0 - self.beta1) * gradient;
+                *second = self.beta2 * *second + (1.0 - self.beta2) * gradient * gradient;
+                let first_estimate = *first / first_correction;
+                let second_estimate = *second / second_correction;
+                *value -=
+                    self.learning_rate * first_estimate / (second_estimate.sqrt() + self.epsilon);
+            }
+        }
+    }
+}
+
+fn resize_state(state: &mut Vec<Vec<f32>>, gradients: &[&[f32]]) {
+    if state.len() != gradients.len()
+        || state
+            .iter()
+            .zip(gradients.iter())
+            .any(|(state, gradient)| state.len() != gradient.len())
+    {
+        *state = gradients
+            .iter()
+            .map(|gradient| vec![0.0; gradient.len()])
+            .collect();
+    }
+}
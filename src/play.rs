@@ -0,0 +1,154 @@
+//! Lets a person defend the cannon with the keyboard: the arrows rotate it and space fires. The
+//! world is the same [`CannonEnv`] the networks play, stepped at its fixed time step in real
+//! time, so scores are comparable with theirs.
+
+use std::{io, path::Path, sync::Arc};
+
+use raylib::{
+    color::Color,
+    consts::KeyboardKey,
+    prelude::{RaylibDraw, RaylibDrawHandle},
+};
+
+use crate::{
+    config::ExperimentConfig,
+    demonstration::{self, Demonstration},
+    entity::Point,
+    environment::CannonEnv,
+    world_view::{self, FixedStepClock},
+};
+
+/// A sixth of a second of play per frame at most.
+const MAX_STEPS_PER_FRAME: usize = 10;
+
+/// Plays episodes from `seed` upwards until the window is closed, then saves every step to
+/// `record` if given.
+pub fn run_play(
+    config: ExperimentConfig,
+    seed: u64,
+    dimensions: Point,
+    record: Option<&Path>,
+) -> Result<(), io::Error> {
+    let delta_time = config.fast_delta_time;
+    let mut demonstration = Demonstration::new(config.clone());
+    let mut environment = CannonEnv::new(Arc::new(config), dimensions.clone());
+
+    let (mut rl, thread) = raylib::init()
+        .size(dimensions.x as i32, dimensions.y as i32)
+        .title("AI Cannon - Play")
+        .build();
+    rl.set_target_fps(60);
+
+    let mut episode_seed = seed;
+    let mut observation = environment.reset(episode_seed);
+    demonstration.start_episode(episode_seed);
+    let mut is_done = false;
+    let mut clock = FixedStepClock::new(delta_time, MAX_STEPS_PER_FRAME);
+
+    while !rl.window_should_close() {
+        if is_done {
+            if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                episode_seed += 1;
+                observation = environment.reset(episode_seed);
+                demonstration.start_episode(episode_seed);
+                is_done = false;
+                clock.reset();
+            }
+        } else {
+            let action = demonstration::keyboard_action(
+                rl.is_key_down(KeyboardKey::KEY_LEFT),
+                rl.is_key_down(KeyboardKey::KEY_RIGHT),
+                rl.is_key_down(KeyboardKey::KEY_SPACE),
+            );
+            clock.advance(rl.get_frame_time(), || {
+                if is_done {
+                    return false;
+                }
+                let (next_observation, reward, done, info) = environment.step(action);
+                demonstration.record(&observation, action, reward);
+                if done {
+                    demonstration.finish_episode(info.enemies_killed);
+                    is_done = true;
+                }
+                observation = next_observation;
+                true
+            });
+        }
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::RAYWHITE);
+        world_view::draw_world(
+            &mut d,
+            environment.cannon(),
+            environment.bullets(),
+            environment.enemies(),
+        );
+        if !observation.can_shoot {
+            draw_cooldown(&mut d, &environment);
+        }
+        draw_hud(&mut d, &environment, is_done);
+    }
+    drop(rl);
+
+    if let Some(path) = record {
+        demonstration.save(path)?;
+        println!(
+            "Recorded {} episodes, {} steps, to {}",
+            demonstration.episodes.len(),
+            demonstration.total_steps(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+/// Rings the cannon while it cannot fire yet.
+fn draw_cooldown(d: &mut RaylibDrawHandle<'_>, environment: &CannonEnv) {
+    let position = &environment.cannon().position;
+    d.draw_circle_lines(
+        position.x as i32,
+        position.y as i32,
+        environment.cannon().radius + 4.0,
+        Color::LIGHTGRAY,
+    );
+}
+fn draw_hud(d: &mut RaylibDrawHandle<'_>, environment: &CannonEnv, is_done: bool) {
+    let info = environment.info();
+    let training_time = environment.config().training_time;
+    d.draw_text(
+        &format!(
+            "Score: {:.1}   Kills: {}   Hits taken: {}   Time: {:.0}/{training_time}s",
+            info.score,
+            info.enemies_killed,
+            info.enemies_reached_cannon,
+            info.elapsed_time.min(training_time)
+        ),
+        10,
+        10,
+        24,
+        Color::BLACK,
+    );
+    d.draw_text(
+        "Left/Right: rotate   Space: fire",
+        10,
+        40,
+        20,
+        Color::DARKGRAY,
+    );
+    if is_done {
+        let center = environment.dimensions();
+        d.draw_text(
+            &format!("Game over! Score {:.1}", info.score),
+            (center.x / 2.0 - 180.0) as i32,
+            (center.y / 2.0 - 120.0) as i32,
+            40,
+            Color::RED,
+        );
+        d.draw_text(
+            "Press Enter to play again or close the window to quit",
+            (center.x / 2.0 - 260.0) as i32,
+            (center.y / 2.0 - 70.0) as i32,
+            20,
+            Color::DARKGRAY,
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Recordings of single episodes. [`CannonEnv`] is deterministic, so a replay only stores the
+//! config, arena size and seed of the episode and the action taken at every step; playing it
+//! back simulates the episode again.
+//!
+//! A replay file starts with [`REPLAY_MAGIC`] and a little-endian `u16` [`REPLAY_VERSION`],
+//! followed by the length in bytes (`u32`) of a JSON [`ReplayHeader`], the header itself, the
+//! number of steps (`u32`) and one byte per step, zlib compressed. Each action byte holds the
+//! [`Rotation`] index in its low two bits and whether the cannon shot in bit 2.
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    ops::ControlFlow,
+    path::Path,
+    sync::Arc,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ExperimentConfig,
+    entity::Point,
+    environment::{Action, CannonEnv, Policy, Rotation, StepInfo},
+    storage,
+};
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"CNRP";
+pub const REPLAY_VERSION: u16 = 1;
+/// Extension of replay files.
+pub const REPLAY_EXTENSION: &str = "replay";
+/// Steps between the saved states a [`ReplayPlayer`] seeks from.
+const KEYFRAME_INTERVAL: usize = 500;
+const SHOOT_BIT: u8 = 0b100;
+
+/// Everything besides the actions that is needed to play an episode again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub config: ExperimentConfig,
+    pub width: f32,
+    pub height: f32,
+    pub seed: u64,
+    /// Where the episode came from, e.g. the generation and AI that played it.
+    pub label: String,
+    /// Final score, to check that playing back reproduces the episode.
+    pub score: f32,
+    pub enemies_killed: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub actions: Vec<Action>,
+}
+
+impl Replay {
+    /// Plays a whole episode with `policy` and records it.
+    pub fn record(
+        config: Arc<ExperimentConfig>,
+        dimensions: Point,
+        seed: u64,
+        label: String,
+        policy: &mut impl Policy,
+    ) -> Self {
+        let mut environment = CannonEnv::new(Arc::clone(&config), dimensions.clone());
+        let mut actions = vec![];
+        let info = environment
+            .run_episode_with(seed, policy, |_, action| {
+                actions.push(action);
+                ControlFlow::Continue(())
+            })
+            .expect("Recording never stops an episode early");
+        Self::from_episode(&config, &dimensions, seed, label, actions, &info)
+    }
+    /// A replay of an episode that was played in an arena of `dimensions` from `seed`, with
+    /// `info` as its final statistics.
+    pub fn from_episode(
+        config: &ExperimentConfig,
+        dimensions: &Point,
+        seed: u64,
+        label: String,
+        actions: Vec<Action>,
+        info: &StepInfo,
+    ) -> Self {
+        Self {
+            header: ReplayHeader {
+                config: config.clone(),
+                width: dimensions.x,
+                height: dimensions.y,
+                seed,
+                label,
+                score: info.score,
+                enemies_killed: info.enemies_killed,
+            },
+            actions,
+        }
+    }
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let header = serde_json::to_vec(&self.header).map_err(|error| error.to_string())?;
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&(self.actions.len() as u32).to_le_bytes());
+
+        let mut encoder = ZlibEncoder::new(bytes, Compression::best());
+        let codes = self
+            .actions
+            .iter()
+            .map(|action| action_code(*action))
+            .collect::<Vec<u8>>();
+        encoder
+            .write_all(&codes)
+            .and_then(|()| encoder.finish())
+            .map_err(|error| error.to_string())
+    }
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut remaining = bytes;
+        let mut take = |length: usize| {
+            if remaining.len() < length {
+                return Err("Replay is truncated".to_string());
+            }
+            let (taken, rest) = remaining.split_at(length);
+            remaining = rest;
+            Ok(taken)
+        };
+        if take(4)? != REPLAY_MAGIC {
+            return Err("Not a replay file".to_string());
+        }
+        let version = u16::from_le_bytes(take(2)?.try_into().unwrap());
+        if version != REPLAY_VERSION {
+            return Err(format!(
+                "Unsupported replay version {version}, expected {REPLAY_VERSION}"
+            ));
+        }
+        let header_length = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let header: ReplayHeader = serde_json::from_slice(take(header_length)?)
+            .map_err(|error| format!("Invalid replay header: {error}"))?;
+        header.config.validate()?;
+        let total_steps = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+
+        let mut codes = vec![];
+        ZlibDecoder::new(remaining)
+            .take(total_steps as u64 + 1)
+            .read_to_end(&mut codes)
+            .map_err(|error| format!("Invalid replay actions: {error}"))?;
+        if codes.len() != total_steps {
+            return Err(format!(
+                "Replay holds {} actions but its header says {total_steps}",
+                codes.len()
+            ));
+        }
+        let actions = codes
+            .into_iter()
+            .map(decode_action)
+            .collect::<Result<Vec<Action>, String>>()?;
+        Ok(Self { header, actions })
+    }
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let bytes = self
+            .encode()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        storage::write_atomically(path, &bytes)
+    }
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        Self::decode(&fs::read(path)?).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {error}", path.display()),
+            )
+        })
+    }
+}
+
+/// Plays a [`Replay`] back one step at a time and jumps to any step by simulating forward from
+/// the closest saved state.
+pub struct ReplayPlayer {
+    actions: Vec<Action>,
+    environment: CannonEnv,
+    position: usize,
+    /// The world before step `i * KEYFRAME_INTERVAL`.
+    keyframes: Vec<CannonEnv>,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: &Replay) -> Self {
+        let header = &replay.header;
+        let mut environment = CannonEnv::new(
+            Arc::new(header.config.clone()),
+            Point {
+                x: header.width,
+                y: header.height,
+            },
+        );
+        environment.reset(header.seed);
+        let mut keyframes = vec![];
+        for (step, action) in replay.actions.iter().enumerate() {
+            if step % KEYFRAME_INTERVAL == 0 {
+                keyframes.push(environment.clone());
+            }
+            environment.step(*action);
+        }
+        let environment = keyframes.first().cloned().unwrap_or(environment);
+        Self {
+            actions: replay.actions.clone(),
+            environment,
+            position: 0,
+            keyframes,
+        }
+    }
+    /// The world after the first [`Self::position`] steps.
+    pub fn environment(&self) -> &CannonEnv {
+        &self.environment
+    }
+    /// Number of steps played so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    pub fn total_steps(&self) -> usize {
+        self.actions.len()
+    }
+    pub fn is_finished(&self) -> bool {
+        self.position == self.actions.len()
+    }
+    /// Plays the next step, or returns `false` at the end of the episode.
+    pub fn advance(&mut self) -> bool {
+        let Some(action) = self.actions.get(self.position) else {
+            return false;
+        };
+        self.environment.step(*action);
+        self.position += 1;
+        true
+    }
+    /// Moves to the world after `position` steps, clamped to the episode.
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.actions.len());
+        let keyframe = (position / KEYFRAME_INTERVAL).min(self.keyframes.len().saturating_sub(1));
+        if position < self.position || keyframe * KEYFRAME_INTERVAL > self.position {
+            if let Some(environment) = self.keyframes.get(keyframe) {
+                self.environment = environment.clone();
+                self.position = keyframe * KEYFRAME_INTERVAL;
+            }
+        }
+        while self.position < position {
+            self.advance();
+        }
+    }
+}
+
+fn action_code(action: Action) -> u8 {
+    action.rotation.index() as u8 | if action.shoot { SHOOT_BIT } else { 0 }
+}
+fn decode_action(code: u8) -> Result<Action, String> {
+    let rotation = Rotation::from_index(usize::from(code & 0b11))
+        .filter(|_| code & !(SHOOT_BIT | 0b11) == 0)
+        .ok_or_else(|| format!("Invalid replay action code {code}"))?;
+    Ok(Action {
+        rotation,
+        shoot: code & SHOOT_BIT != 0,
+    })
+}
@@ -0,0 +1,166 @@
+//! Plays a [`Replay`] back in a window through the entities' [`Sprite::draw`].
+//!
+//! Space pauses, the left and right arrows step one simulation step, `[` and `]` jump a second,
+//! the up and down arrows double or halve the speed, Home and End go to either end, and
+//! clicking or dragging on the bar at the bottom scrubs.
+
+use raylib::{
+    color::Color,
+    consts::{KeyboardKey, MouseButton},
+    ffi::Rectangle,
+    prelude::{RaylibDraw, RaylibDrawHandle},
+};
+
+use crate::{
+    replay::{Replay, ReplayPlayer},
+    world_view::{self, FixedStepClock},
+};
+
+const MIN_SPEED: f32 = 1.0 / 16.0;
+const MAX_SPEED: f32 = 16.0;
+/// Enough steps per frame for the highest speed at low frame rates.
+const MAX_STEPS_PER_FRAME: usize = 5000;
+const BAR_HEIGHT: f32 = 16.0;
+const BAR_MARGIN: f32 = 20.0;
+
+pub fn run_replay_viewer(replay: &Replay) {
+    let header = &replay.header;
+    let delta_time = header.config.fast_delta_time;
+    let mut player = ReplayPlayer::new(replay);
+
+    let (mut rl, thread) = raylib::init()
+        .size(header.width as i32, header.height as i32)
+        .title("AI Cannon - Replay")
+        .build();
+    rl.set_target_fps(60);
+
+    let mut is_paused = false;
+    let mut speed = 1.0_f32;
+    let mut clock = FixedStepClock::new(delta_time, MAX_STEPS_PER_FRAME);
+    let steps_per_second = (1.0 / delta_time).round() as usize;
+    let bar = Rectangle {
+        x: BAR_MARGIN,
+        y: header.height - BAR_MARGIN - BAR_HEIGHT,
+        width: header.width - 2.0 * BAR_MARGIN,
+        height: BAR_HEIGHT,
+    };
+
+    while !rl.window_should_close() {
+        if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            if player.is_finished() {
+                player.seek(0);
+            }
+            is_paused = !is_paused;
+            clock.reset();
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            speed = (speed * 2.0).min(MAX_SPEED);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            speed = (speed / 2.0).max(MIN_SPEED);
+        }
+        let position = player.position();
+        let target = if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+            is_paused = true;
+            Some(position + 1)
+        } else if rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+            is_paused = true;
+            Some(position.saturating_sub(1))
+        } else if rl.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET) {
+            Some(position + steps_per_second)
+        } else if rl.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) {
+            Some(position.saturating_sub(steps_per_second))
+        } else if rl.is_key_pressed(KeyboardKey::KEY_HOME) {
+            Some(0)
+        } else if rl.is_key_pressed(KeyboardKey::KEY_END) {
+            Some(player.total_steps())
+        } else if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            let mouse = rl.get_mouse_position();
+            let on_bar = mouse.y >= bar.y - BAR_HEIGHT && mouse.y <= bar.y + 2.0 * BAR_HEIGHT;
+            on_bar.then(|| {
+                let fraction = ((mouse.x - bar.x) / bar.width).clamp(0.0, 1.0);
+                (fraction * player.total_steps() as f32).round() as usize
+            })
+        } else {
+            None
+        };
+        if let Some(target) = target {
+            player.seek(target);
+            clock.reset();
+        }
+
+        if !is_paused {
+            clock.advance(rl.get_frame_time() * speed, || {
+                is_paused = !player.advance();
+                !is_paused
+            });
+        }
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::RAYWHITE);
+        let environment = player.environment();
+        world_view::draw_world(
+            &mut d,
+            environment.cannon(),
+            environment.bullets(),
+            environment.enemies(),
+        );
+        draw_hud(&mut d, replay, &player, is_paused, speed);
+        draw_bar(&mut d, &player, bar);
+    }
+}
+fn draw_hud(
+    d: &mut RaylibDrawHandle<'_>,
+    replay: &Replay,
+    player: &ReplayPlayer,
+    is_paused: bool,
+    speed: f32,
+) {
+    let info = player.environment().info();
+    let header = &replay.header;
+    d.draw_text(&header.label, 10, 10, 24, Color::BLACK);
+    d.draw_text(
+        &format!(
+            "Time: {:.2}/{}s   Score: {:.1}/{:.1}   Kills: {}/{}",
+            info.elapsed_time,
+            header.config.training_time,
+            info.score,
+            header.score,
+            info.enemies_killed,
+            header.enemies_killed
+        ),
+        10,
+        40,
+        20,
+        Color::BLACK,
+    );
+    d.draw_text(
+        &format!(
+            "{}   Speed: {speed}x",
+            if is_paused { "Paused" } else { "Playing" }
+        ),
+        10,
+        65,
+        20,
+        Color::DARKGRAY,
+    );
+    d.draw_text(
+        "Space: pause   Left/Right: step   [ ]: 1s   Up/Down: speed   Home/End   Click bar: scrub",
+        10,
+        90,
+        16,
+        Color::GRAY,
+    );
+}
+fn draw_bar(d: &mut RaylibDrawHandle<'_>, player: &ReplayPlayer, bar: Rectangle) {
+    d.draw_rectangle_rec(bar, Color::LIGHTGRAY);
+    let fraction = player.position() as f32 / player.total_steps().max(1) as f32;
+    d.draw_rectangle_rec(
+        Rectangle {
+            width: bar.width * fraction,
+            ..bar
+        },
+        Color::BLUE,
+    );
+    d.draw_rectangle_lines_ex(bar, 1.0, Color::DARKGRAY);
+}
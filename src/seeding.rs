@@ -0,0 +1,32 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Independent sources of randomness derived from the master seed.
+#[derive(Clone, Copy)]
+pub enum RngStream {
+    DirectionAis = 1,
+    ShootingAis = 2,
+    Episode = 3,
+    Mutation = 4,
+    /// Episodes played by [`crate::compare`].
+    Comparison = 5,
+}
+
+/// Derives an independent RNG for one stream, generation and AI from the master seed, so the
+/// numbers an AI sees never depend on how threads happen to be scheduled.
+pub fn stream_rng(master_seed: u64, stream: RngStream, generation: usize, index: usize) -> StdRng {
+    StdRng::seed_from_u64(stream_seed(master_seed, stream, generation, index))
+}
+/// The seed [`stream_rng`] would use, for APIs such as [`crate::environment::CannonEnv::reset`].
+pub fn stream_seed(master_seed: u64, stream: RngStream, generation: usize, index: usize) -> u64 {
+    let mut seed = master_seed;
+    for value in [stream as u64, generation as u64, index as u64] {
+        seed = split_mix_64(seed ^ split_mix_64(value));
+    }
+    seed
+}
+fn split_mix_64(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
@@ -0,0 +1,237 @@
+use std::cmp::Ordering;
+
+use rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+/// The scores of one generation and their ranking, which is sorted once and shared by every
+/// parent selected from them.
+pub struct RankedScores<'a> {
+    pub scores: &'a [f32],
+    /// Indices from the highest to the lowest score, as [`ranked_indices`] returns them.
+    pub ranking: Vec<usize>,
+}
+
+impl<'a> RankedScores<'a> {
+    pub fn new(scores: &'a [f32]) -> Self {
+        Self {
+            scores,
+            ranking: ranked_indices(scores),
+        }
+    }
+}
+
+/// Decides which AIs carry over unchanged and which AIs breed the rest of the next generation.
+pub trait SelectionStrategy {
+    /// Indices of the AIs that are kept untouched; every other AI is replaced by a child.
+    fn survivors(&self, scores: &RankedScores) -> Box<[usize]>;
+    /// Picks the index of one parent for a new child.
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize;
+}
+
+/// Keeps the best `fraction` of the population and breeds uniformly from it.
+pub struct Truncation {
+    pub fraction: f32,
+}
+
+impl Truncation {
+    fn total_survivors(&self, total_ais: usize) -> usize {
+        ((self.fraction * total_ais as f32).ceil() as usize).clamp(1, total_ais.max(1))
+    }
+}
+
+impl SelectionStrategy for Truncation {
+    fn survivors(&self, scores: &RankedScores) -> Box<[usize]> {
+        let total_survivors = self.total_survivors(scores.ranking.len());
+        scores.ranking[..total_survivors].into()
+    }
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize {
+        let total_survivors = self.total_survivors(scores.ranking.len());
+        scores.ranking[rng.gen_range(0..total_survivors)]
+    }
+}
+
+/// Picks the best of `size` AIs drawn uniformly with replacement.
+pub struct Tournament {
+    pub size: usize,
+}
+
+impl SelectionStrategy for Tournament {
+    fn survivors(&self, _scores: &RankedScores) -> Box<[usize]> {
+        Box::new([])
+    }
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize {
+        let scores = scores.scores;
+        (0..self.size.max(1))
+            .map(|_| rng.gen_range(0..scores.len()))
+            .max_by(|a, b| compare_scores(scores[*a], scores[*b]))
+            .expect("Tournaments have at least one entrant")
+    }
+}
+
+/// Fitness-proportional selection. Scores are shifted so that the worst AI has a small positive
+/// weight, since scores can be negative.
+pub struct Roulette;
+
+impl SelectionStrategy for Roulette {
+    fn survivors(&self, _scores: &RankedScores) -> Box<[usize]> {
+        Box::new([])
+    }
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize {
+        let scores = scores.scores;
+        let min = scores
+            .iter()
+            .cloned()
+            .filter(|score| score.is_finite())
+            .fold(f32::MAX, f32::min);
+        let weights = scores
+            .iter()
+            .map(|score| {
+                if score.is_finite() {
+                    score - min + f32::EPSILON
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Box<[f32]>>();
+        pick_weighted(&weights, rng)
+    }
+}
+
+/// Selection proportional to rank, with the best of `n` AIs weighted `n` and the worst `1`.
+pub struct Rank;
+
+impl SelectionStrategy for Rank {
+    fn survivors(&self, _scores: &RankedScores) -> Box<[usize]> {
+        Box::new([])
+    }
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize {
+        let total_ais = scores.ranking.len();
+        let mut weights = vec![0.0; total_ais].into_boxed_slice();
+        for (rank, index) in scores.ranking.iter().enumerate() {
+            weights[*index] = (total_ais - rank) as f32;
+        }
+        pick_weighted(&weights, rng)
+    }
+}
+
+/// Keeps the best `count` AIs untouched on top of whatever `parents` keeps, and leaves choosing
+/// parents to `parents`.
+pub struct Elitism {
+    pub count: usize,
+    pub parents: Box<dyn SelectionStrategy + Send + Sync>,
+}
+
+impl SelectionStrategy for Elitism {
+    fn survivors(&self, scores: &RankedScores) -> Box<[usize]> {
+        let mut survivors = scores
+            .ranking
+            .iter()
+            .copied()
+            .take(self.count)
+            .collect::<Vec<usize>>();
+        for survivor in self.parents.survivors(scores).iter() {
+            if !survivors.contains(survivor) {
+                survivors.push(*survivor);
+            }
+        }
+        survivors.into_boxed_slice()
+    }
+    fn select_parent(&self, scores: &RankedScores, rng: &mut StdRng) -> usize {
+        self.parents.select_parent(scores, rng)
+    }
+}
+
+/// The selection strategy of an experiment as written in its config file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Selection {
+    Truncation {
+        fraction: f32,
+    },
+    Tournament {
+        size: usize,
+    },
+    Roulette,
+    Rank,
+    Elitism {
+        count: usize,
+        parents: Box<Selection>,
+    },
+}
+
+impl Default for Selection {
+    /// Keeps the better half, as the trainer always did.
+    fn default() -> Self {
+        Selection::Truncation { fraction: 0.5 }
+    }
+}
+
+impl Selection {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            // Keeping every AI would leave none to replace, so evolution would stop.
+            Selection::Truncation { fraction } if !(*fraction > 0.0 && *fraction < 1.0) => Err(
+                format!("Truncation fraction must be in (0, 1), got {fraction}"),
+            ),
+            Selection::Tournament { size: 0 } => {
+                Err("Tournament size must be at least 1".to_string())
+            }
+            Selection::Elitism { parents, .. } => parents.validate(),
+            _ => Ok(()),
+        }
+    }
+    /// The most AIs any [`Elitism`] in this selection keeps untouched.
+    pub fn elite_count(&self) -> usize {
+        match self {
+            Selection::Elitism { count, parents } => (*count).max(parents.elite_count()),
+            _ => 0,
+        }
+    }
+    pub fn strategy(&self) -> Box<dyn SelectionStrategy + Send + Sync> {
+        match self {
+            Selection::Truncation { fraction } => Box::new(Truncation {
+                fraction: *fraction,
+            }),
+            Selection::Tournament { size } => Box::new(Tournament { size: *size }),
+            Selection::Roulette => Box::new(Roulette),
+            Selection::Rank => Box::new(Rank),
+            Selection::Elitism { count, parents } => Box::new(Elitism {
+                count: *count,
+                parents: parents.strategy(),
+            }),
+        }
+    }
+}
+
+/// Indices sorted from the highest to the lowest score, ties broken by index.
+pub fn ranked_indices(scores: &[f32]) -> Vec<usize> {
+    let mut indices = (0..scores.len()).collect::<Vec<usize>>();
+    indices.sort_by(|a, b| compare_scores(scores[*b], scores[*a]));
+    indices
+}
+/// A total order on scores in which NaN ranks below every other score, so that sorting never
+/// sees an inconsistent comparison and a NaN fitness is never picked as the best.
+fn compare_scores(a: f32, b: f32) -> Ordering {
+    let key = |score: f32| {
+        if score.is_nan() {
+            f32::NEG_INFINITY
+        } else {
+            score
+        }
+    };
+    key(a).total_cmp(&key(b))
+}
+fn pick_weighted(weights: &[f32], rng: &mut StdRng) -> usize {
+    let total = weights.iter().sum::<f32>();
+    if !(total.is_finite() && total > 0.0) {
+        return rng.gen_range(0..weights.len());
+    }
+    let mut remaining = rng.gen_range(0.0..total);
+    for (index, weight) in weights.iter().enumerate() {
+        if remaining < *weight {
+            return index;
+        }
+        remaining -= weight;
+    }
+    weights.len() - 1
+}
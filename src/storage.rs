@@ -0,0 +1,427 @@
+//! Reading and writing saved populations.
+//!
+//! Network arrays are stored either as JSON or in a compact binary format, detected on load by
+//! its magic bytes. The binary format is, all integers and floats little-endian:
+//!
+//! - the magic bytes `CNAI`, a `u16` format version and a `u8` compression flag (0 for none,
+//!   1 for zlib), followed by the possibly compressed body;
+//! - the body: a `u32` network count, then per network its `u32` input size, output size and
+//!   layer count, then per layer a `u8` activation code, `u32` rows and columns, the weights as
+//!   column-major `f32`s and one `f32` bias per row.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use na::{DMatrix, DVector};
+
+use crate::neural_network::{Activation, NeuralNetwork};
+
+pub const BINARY_MAGIC: &[u8; 4] = b"CNAI";
+pub const BINARY_VERSION: u16 = 1;
+/// Activations in the order of their binary codes; new activations must be appended.
+const ACTIVATION_CODES: [Activation; 6] = [
+    Activation::Tanh,
+    Activation::Sigmoid,
+    Activation::Relu,
+    Activation::LeakyRelu,
+    Activation::Linear,
+    Activation::Softmax,
+];
+
+/// Why a saved network file could not be used for a run.
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    /// The file holds `found` networks where `expected` were needed.
+    PopulationSize {
+        path: PathBuf,
+        expected: usize,
+        found: usize,
+    },
+    InputSize {
+        path: PathBuf,
+        ai_index: usize,
+        expected: usize,
+        found: usize,
+    },
+    OutputSize {
+        path: PathBuf,
+        ai_index: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, error } => write!(f, "Cannot read {}: {error}", path.display()),
+            LoadError::Parse { path, message } => {
+                write!(f, "Cannot parse {}: {message}", path.display())
+            }
+            LoadError::PopulationSize {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} holds {found} AIs but {expected} are needed",
+                path.display()
+            ),
+            LoadError::InputSize {
+                path,
+                ai_index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "AI {ai_index} in {} takes {found} inputs but the config has {expected} view rays",
+                path.display()
+            ),
+            LoadError::OutputSize {
+                path,
+                ai_index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "AI {ai_index} in {} has {found} outputs but {expected} are needed",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<LoadError> for io::Error {
+    fn from(error: LoadError) -> Self {
+        let kind = match &error {
+            LoadError::Io { error, .. } => error.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// What to do when saved networks do not fit the run they are loaded into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MismatchPolicy {
+    /// Stop with an error.
+    #[default]
+    Error,
+    /// Warn, move the saved files to `*.bak` and start from a random population instead.
+    Random,
+    /// Truncate or repeat the saved AIs to the requested population size. Networks of the wrong
+    /// shape are still an error.
+    Adapt,
+}
+
+/// How network arrays are written; loading accepts every format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NetworkFormat {
+    #[default]
+    Json,
+    Binary,
+    /// The binary format with a zlib-compressed body.
+    CompressedBinary,
+}
+
+impl NetworkFormat {
+    /// Extension of the default file names.
+    pub fn extension(self) -> &'static str {
+        match self {
+            NetworkFormat::Json => "json",
+            NetworkFormat::Binary | NetworkFormat::CompressedBinary => "bin",
+        }
+    }
+}
+
+/// Loads a saved network array in any format, or returns `None` if the file does not exist.
+pub fn load_networks(path: &Path) -> Result<Option<Box<[NeuralNetwork]>>, LoadError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(LoadError::Io {
+                path: path.to_path_buf(),
+                error,
+            })
+        }
+    };
+    decode_networks(&bytes)
+        .map(Some)
+        .map_err(|message| LoadError::Parse {
+            path: path.to_path_buf(),
+            message,
+        })
+}
+/// Atomically writes `networks` to `path` in `format`.
+pub fn save_networks(
+    path: &Path,
+    networks: &[NeuralNetwork],
+    format: NetworkFormat,
+) -> Result<(), io::Error> {
+    write_atomically(path, &encode_networks(networks, format)?)
+}
+pub fn encode_networks(
+    networks: &[NeuralNetwork],
+    format: NetworkFormat,
+) -> Result<Vec<u8>, io::Error> {
+    let compressed = match format {
+        NetworkFormat::Json => return Ok(serde_json::to_string_pretty(networks)?.into_bytes()),
+        NetworkFormat::Binary => false,
+        NetworkFormat::CompressedBinary => true,
+    };
+    let body = encode_body(networks)?;
+    let mut bytes = Vec::with_capacity(body.len() + 7);
+    bytes.extend_from_slice(BINARY_MAGIC);
+    bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+    bytes.push(u8::from(compressed));
+    if compressed {
+        let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+        encoder.write_all(&body)?;
+        encoder.finish()
+    } else {
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+}
+/// Decodes the binary format if `bytes` start with [`BINARY_MAGIC`], or JSON otherwise.
+///
+/// A compressed body is inflated only as far as the shapes it declares need, so a corrupt or
+/// hostile file cannot expand into more memory than its networks would take.
+pub fn decode_networks(bytes: &[u8]) -> Result<Box<[NeuralNetwork]>, String> {
+    let Some(header) = bytes.strip_prefix(BINARY_MAGIC) else {
+        return serde_json::from_slice(bytes).map_err(|error| error.to_string());
+    };
+    let mut reader = BinaryReader { reader: header };
+    let version = u16::from_le_bytes(reader.take()?);
+    if version != BINARY_VERSION {
+        return Err(format!(
+            "Unsupported binary format version {version}, expected {BINARY_VERSION}"
+        ));
+    }
+    match reader.u8()? {
+        0 => decode_body(reader),
+        1 => decode_body(BinaryReader {
+            reader: ZlibDecoder::new(reader.reader),
+        }),
+        flag => Err(format!("Unknown compression flag {flag}")),
+    }
+}
+fn encode_body(networks: &[NeuralNetwork]) -> Result<Vec<u8>, io::Error> {
+    let mut body = vec![];
+    push_u32(&mut body, networks.len())?;
+    for network in networks {
+        push_u32(&mut body, network.input_size())?;
+        push_u32(&mut body, network.output_size())?;
+        push_u32(&mut body, network.weights().len())?;
+        for ((weight, bias), activation) in network
+            .weights()
+            .iter()
+            .zip(network.biases())
+            .zip(network.activations())
+        {
+            let code = ACTIVATION_CODES
+                .iter()
+                .position(|candidate| candidate == activation)
+                .expect("Every activation has a binary code");
+            body.push(code as u8);
+            push_u32(&mut body, weight.nrows())?;
+            push_u32(&mut body, weight.ncols())?;
+            for value in weight.iter().chain(bias.iter()) {
+                body.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    Ok(body)
+}
+/// Appends a count or size, which the binary format stores in 32 bits.
+fn push_u32(body: &mut Vec<u8>, value: usize) -> Result<(), io::Error> {
+    let value = u32::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{value} does not fit in the binary format's 32-bit sizes"),
+        )
+    })?;
+    body.extend_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+fn decode_body(mut reader: BinaryReader<impl Read>) -> Result<Box<[NeuralNetwork]>, String> {
+    let total_networks = reader.u32()?;
+    // Counts are not trusted for allocations, every network and layer must be read first.
+    let mut networks = vec![];
+    for ai_index in 0..total_networks {
+        let input_size = reader.u32()?;
+        let output_size = reader.u32()?;
+        let total_layers = reader.u32()?;
+        let mut weights = vec![];
+        let mut biases = vec![];
+        let mut activations = vec![];
+        for _ in 0..total_layers {
+            let code = reader.u8()?;
+            activations.push(
+                *ACTIVATION_CODES
+                    .get(code as usize)
+                    .ok_or_else(|| format!("Unknown activation code {code}"))?,
+            );
+            let rows = reader.u32()?;
+            let columns = reader.u32()?;
+            let total_weights = rows
+                .checked_mul(columns)
+                .ok_or_else(|| format!("Layer of {rows}x{columns} weights is too large"))?;
+            weights.push(DMatrix::from_vec(
+                rows,
+                columns,
+                reader.f32s(total_weights)?,
+            ));
+            biases.push(DVector::from_vec(reader.f32s(rows)?));
+        }
+        networks.push(
+            NeuralNetwork::from_parts(
+                input_size,
+                output_size,
+                weights.into_boxed_slice(),
+                biases.into_boxed_slice(),
+                activations.into_boxed_slice(),
+            )
+            .map_err(|error| format!("AI {ai_index}: {error}"))?,
+        );
+    }
+    if reader.has_more()? {
+        return Err("Unexpected bytes after the last network".to_string());
+    }
+    Ok(networks.into_boxed_slice())
+}
+
+/// Reads little-endian values from the front of a byte slice or a decompressing stream.
+struct BinaryReader<R> {
+    reader: R,
+}
+
+impl<R: Read> BinaryReader<R> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut value = [0; N];
+        self.reader.read_exact(&mut value).map_err(read_error)?;
+        Ok(value)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take::<1>()?[0])
+    }
+    fn u32(&mut self) -> Result<usize, String> {
+        Ok(u32::from_le_bytes(self.take()?) as usize)
+    }
+    /// Reads `count` floats, growing the buffer only as bytes actually arrive.
+    fn f32s(&mut self, count: usize) -> Result<Vec<f32>, String> {
+        let length = count.saturating_mul(4);
+        let mut bytes = vec![];
+        (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        if bytes.len() != length {
+            return Err("Unexpected end of file".to_string());
+        }
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+    fn has_more(&mut self) -> Result<bool, String> {
+        let mut byte = [0];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(read) => return Ok(read > 0),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(read_error(error)),
+            }
+        }
+    }
+}
+fn read_error(error: io::Error) -> String {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        "Unexpected end of file".to_string()
+    } else {
+        format!("Corrupt compressed body: {error}")
+    }
+}
+/// Checks that the file holds at least one network and that every network has the given input
+/// and output sizes.
+pub fn check_networks(
+    path: &Path,
+    networks: &[NeuralNetwork],
+    input_size: usize,
+    output_size: usize,
+) -> Result<(), LoadError> {
+    if networks.is_empty() {
+        return Err(LoadError::PopulationSize {
+            path: path.to_path_buf(),
+            expected: 1,
+            found: 0,
+        });
+    }
+    for (ai_index, network) in networks.iter().enumerate() {
+        if network.input_size() != input_size {
+            return Err(LoadError::InputSize {
+                path: path.to_path_buf(),
+                ai_index,
+                expected: input_size,
+                found: network.input_size(),
+            });
+        }
+        if network.output_size() != output_size {
+            return Err(LoadError::OutputSize {
+                path: path.to_path_buf(),
+                ai_index,
+                expected: output_size,
+                found: network.output_size(),
+            });
+        }
+    }
+    Ok(())
+}
+/// Truncates `networks` to `total_ais`, or repeats them in order until there are that many.
+pub fn adapt_population(networks: Box<[NeuralNetwork]>, total_ais: usize) -> Box<[NeuralNetwork]> {
+    if networks.len() == total_ais {
+        return networks;
+    }
+    networks.iter().cycle().take(total_ais).cloned().collect()
+}
+/// Renames `path` to `path.bak`, replacing an older backup, and returns the new path. Returns
+/// `None` if there is nothing to back up.
+pub fn back_up(path: &Path) -> Result<Option<PathBuf>, io::Error> {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    let backup_path = PathBuf::from(backup_path);
+    match fs::rename(path, &backup_path) {
+        Ok(()) => Ok(Some(backup_path)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`, so that an
+/// interrupted write leaves the previous version intact.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)
+}
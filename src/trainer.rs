@@ -0,0 +1,368 @@
+//! The evolutionary trainer: runs every AI of a [`SharedResources`] population through its
+//! episodes on a fixed worker pool and breeds the next generation with the configured selection
+//! strategy.
+
+use std::{
+    fs, io,
+    num::NonZero,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rand::rngs::StdRng;
+use typed_floats::Positive;
+
+use crate::{
+    environment::{Action, CannonEnv, NetworkPolicy, Policy, StepInfo},
+    metrics::{GenerationMetrics, MetricsLog},
+    multi_threading::SharedResources,
+    neural_network::{Crossover, NeuralNetwork},
+    new_dynamic_array,
+    replay::{Replay, REPLAY_EXTENSION},
+    seeding::{stream_rng, stream_seed, RngStream},
+    selection::RankedScores,
+    worker_pool::Job,
+};
+
+/// How often a worker playing the selected AI publishes a snapshot of its world, about once per
+/// rendered frame.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Controls how [`run_simulation`] drives the population.
+pub struct SimulationOptions {
+    /// Time to wait before the first generation starts, e.g. while a window opens.
+    pub startup_delay: Duration,
+    /// Stop after this many generations, or run until `is_running` is cleared.
+    pub max_generations: Option<usize>,
+    /// Whether to mutate the population between generations.
+    pub evolve: bool,
+    /// Where to append a [`GenerationMetrics`] record after every generation.
+    pub metrics_log: Option<MetricsLog>,
+    /// Save the population and a checkpoint after every this many generations.
+    pub checkpoint_every: Option<NonZero<usize>>,
+    /// Directory to save a replay of every generation's best episode in.
+    pub replay_dir: Option<PathBuf>,
+}
+
+/// Makes Ctrl-C stop training gracefully, so that the caller still gets to save the population.
+pub fn stop_on_ctrl_c(shared_resources: &SharedResources) -> Result<(), io::Error> {
+    let is_running = Arc::clone(&shared_resources.is_running);
+    ctrlc::set_handler(move || {
+        println!("Interrupted, finishing up");
+        is_running.store(false, Ordering::SeqCst);
+    })
+    .map_err(io::Error::other)
+}
+/// Trains without a window until `options.max_generations` or Ctrl-C, whichever comes first.
+pub fn run_headless(
+    shared_resources: SharedResources,
+    options: SimulationOptions,
+) -> Result<(), io::Error> {
+    stop_on_ctrl_c(&shared_resources)?;
+
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    let simulation = run_simulation(shared_resources, options);
+    simulation.join().expect("Simulation panicked");
+    Ok(())
+}
+/// Spawns the thread that plays generation after generation until `is_running` is cleared.
+pub fn run_simulation(
+    shared_resources: SharedResources,
+    options: SimulationOptions,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut options = options;
+        thread::sleep(options.startup_delay);
+        let start = Instant::now();
+        let previous_wall_time = *lock_with_error!(shared_resources.wall_time);
+        let config_hash = shared_resources.config.hash();
+        while shared_resources.is_running.load(Ordering::SeqCst) {
+            run_generation(&shared_resources);
+            if shared_resources.is_running.load(Ordering::SeqCst) {
+                let generation = shared_resources.generation.load(Ordering::SeqCst);
+                let wall_time = previous_wall_time + start.elapsed();
+                *lock_with_error!(shared_resources.wall_time) = wall_time;
+                let metrics = GenerationMetrics::new(
+                    generation,
+                    &lock_with_error!(&shared_resources.ai_scores),
+                    &lock_with_error!(&shared_resources.episode_totals),
+                    wall_time,
+                    config_hash,
+                );
+                println!(
+                    "Generation {generation}: best score {:.2}, mean {:.2}",
+                    metrics.max_score, metrics.mean_score
+                );
+                if let Some(metrics_log) = options.metrics_log.as_mut() {
+                    if let Err(error) = metrics_log.append(&metrics) {
+                        eprintln!("Failed to write generation metrics: {error}");
+                    }
+                }
+                if let Some(replay_dir) = options.replay_dir.as_deref() {
+                    match save_best_replay(&shared_resources, generation, replay_dir) {
+                        Ok(path) => println!("Saved best episode to {}", path.display()),
+                        Err(error) => eprintln!("Failed to save replay: {error}"),
+                    }
+                }
+                {
+                    let ai_scores = lock_with_error!(&shared_resources.ai_scores).clone();
+                    lock_with_error!(&shared_resources.fitness_history).push(ai_scores);
+                }
+                if options.evolve {
+                    evolve_ais(&shared_resources);
+                }
+
+                let generation = shared_resources.generation.fetch_add(1, Ordering::SeqCst) + 1;
+                if options
+                    .checkpoint_every
+                    .is_some_and(|every| generation.is_multiple_of(every.into()))
+                {
+                    match shared_resources.save_ais() {
+                        Ok(()) => println!("Saved checkpoint at generation {generation}"),
+                        Err(error) => eprintln!("Failed to save checkpoint: {error}"),
+                    }
+                }
+                if options
+                    .max_generations
+                    .is_some_and(|max_generations| generation >= max_generations)
+                {
+                    shared_resources.is_running.store(false, Ordering::SeqCst);
+                }
+            }
+        }
+    })
+}
+/// Plays `episodes_per_genome` episodes per AI on the worker pool, stores every episode's score in
+/// `episode_scores` and the aggregated fitness in `ai_scores`.
+pub fn run_generation(shared_resources: &SharedResources) {
+    let generation = shared_resources.generation.load(Ordering::SeqCst);
+    let mut ai_jobs: Vec<Job> = vec![];
+    for ai_index in 0..Into::<usize>::into(*shared_resources.total_ais) {
+        let shared_resources_clone = shared_resources.arc_clone();
+
+        ai_jobs.push(Box::new(move || {
+            let config = &*shared_resources_clone.config;
+            // The job owns its networks and world, so stepping never waits on another thread.
+            let direction_ai =
+                lock_with_error!(shared_resources_clone.direction_ais)[ai_index].clone();
+            let shooting_ai =
+                lock_with_error!(shared_resources_clone.shooting_ais)[ai_index].clone();
+            let mut policy = NetworkPolicy {
+                direction_ai: &direction_ai,
+                shooting_ai: &shooting_ai,
+            };
+            let dimensions = { lock_with_error!(shared_resources_clone.dimensions).clone() };
+            let mut environment =
+                CannonEnv::new(Arc::clone(&shared_resources_clone.config), dimensions);
+
+            let mut scores = Vec::with_capacity(config.episodes_per_genome);
+            let mut totals = StepInfo::default();
+            let mut best_episode: Option<Replay> = None;
+            for episode in 0..config.episodes_per_genome {
+                let episode_seed =
+                    episode_seed(&shared_resources_clone, generation, ai_index, episode);
+                let mut actions = vec![];
+                let Some(info) = play_episode(
+                    &shared_resources_clone,
+                    ai_index,
+                    &mut environment,
+                    &mut policy,
+                    episode_seed,
+                    &mut actions,
+                ) else {
+                    return;
+                };
+                scores.push(info.score);
+                totals += &info;
+                if best_episode
+                    .as_ref()
+                    .is_none_or(|best| info.score > best.header.score)
+                {
+                    best_episode = Some(Replay::from_episode(
+                        config,
+                        environment.dimensions(),
+                        episode_seed,
+                        format!("Generation {generation}, AI {ai_index}, episode {episode}"),
+                        actions,
+                        &info,
+                    ));
+                }
+            }
+            let fitness = config.fitness_aggregate.aggregate(&scores);
+            lock_with_error!(&shared_resources_clone.episode_scores)[ai_index] = scores;
+            lock_with_error!(&shared_resources_clone.episode_totals)[ai_index] = totals;
+            lock_with_error!(&shared_resources_clone.best_episodes)[ai_index] = best_episode;
+            lock_with_error!(&shared_resources_clone.ai_scores)[ai_index] = fitness;
+        }));
+    }
+
+    shared_resources.worker_pool.run_all(ai_jobs);
+}
+/// Saves the highest scoring episode of the last generation, as it was recorded while being
+/// played, as `generation_<generation>.replay` in `replay_dir`.
+pub fn save_best_replay(
+    shared_resources: &SharedResources,
+    generation: usize,
+    replay_dir: &Path,
+) -> Result<PathBuf, io::Error> {
+    let replay = lock_with_error!(&shared_resources.best_episodes)
+        .iter()
+        .flatten()
+        .max_by(|a, b| a.header.score.total_cmp(&b.header.score))
+        .cloned()
+        .ok_or_else(|| io::Error::other("No episode was played"))?;
+    fs::create_dir_all(replay_dir)?;
+    let path = replay_dir.join(format!("generation_{generation}.{REPLAY_EXTENSION}"));
+    replay.save(&path)?;
+    Ok(path)
+}
+/// With shared seeds every AI faces the same spawn sequence in its `episode`-th episode.
+fn episode_seed(
+    shared_resources: &SharedResources,
+    generation: usize,
+    ai_index: usize,
+    episode: usize,
+) -> u64 {
+    let config = &shared_resources.config;
+    let index = if config.shared_episode_seeds {
+        episode
+    } else {
+        ai_index * config.episodes_per_genome + episode
+    };
+    stream_seed(shared_resources.seed, RngStream::Episode, generation, index)
+}
+/// Plays one episode in `environment` and appends every action it takes to `actions`, or returns
+/// `None` if training stopped meanwhile. While `ai_index` is the selected AI, a snapshot of its
+/// world is published for the renderer and, in real time, its steps are paced; every other AI runs
+/// at full speed so that a generation takes about as long as the selected AI's episodes no matter
+/// how many AIs share a worker.
+fn play_episode(
+    shared_resources: &SharedResources,
+    ai_index: usize,
+    environment: &mut CannonEnv,
+    policy: &mut impl Policy,
+    episode_seed: u64,
+    actions: &mut Vec<Action>,
+) -> Option<StepInfo> {
+    let delta_time = Duration::from_secs_f32(shared_resources.config.fast_delta_time);
+    let mut next_step = Instant::now();
+    let mut next_snapshot = Instant::now();
+    let mut is_selected = is_selected_ai(shared_resources, ai_index);
+
+    environment.run_episode_with(episode_seed, policy, |environment, action| {
+        actions.push(action);
+        if !shared_resources.is_running.load(Ordering::SeqCst) {
+            return ControlFlow::Break(());
+        }
+        if Instant::now() >= next_snapshot {
+            is_selected = is_selected_ai(shared_resources, ai_index);
+            if is_selected {
+                *lock_with_error!(shared_resources.selected_world) =
+                    Some(environment.snapshot(ai_index));
+            }
+            next_snapshot = Instant::now() + SNAPSHOT_INTERVAL;
+        }
+        if is_selected && shared_resources.is_real_time.load(Ordering::SeqCst) {
+            // Real time only paces the fixed step so that runs stay reproducible.
+            next_step += delta_time;
+            let now = Instant::now();
+            if next_step > now {
+                thread::sleep(next_step - now);
+            } else if now - next_step > Duration::from_millis(100) {
+                next_step = now;
+            }
+        }
+        ControlFlow::Continue(())
+    })
+}
+fn is_selected_ai(shared_resources: &SharedResources, ai_index: usize) -> bool {
+    *lock_with_error!(shared_resources.selected_ai) == ai_index
+}
+/// Plays generations without evolving until every AI has played `episodes` episodes, and
+/// returns the score of each of them. Every generation plays `episodes_per_genome` episodes per
+/// AI, so the last one may be cut short.
+pub fn evaluate(shared_resources: &SharedResources, episodes: usize) -> Box<[Vec<f32>]> {
+    let total_ais = Into::<usize>::into(*shared_resources.total_ais);
+    let total_generations = episodes.div_ceil(shared_resources.config.episodes_per_genome);
+    let mut episode_scores = new_dynamic_array!(total_ais, vec![], Vec<f32>);
+    for generation in 0..total_generations {
+        run_generation(shared_resources);
+        let generation_scores = lock_with_error!(shared_resources.episode_scores);
+        for (scores, generation_scores) in episode_scores.iter_mut().zip(generation_scores.iter()) {
+            scores.extend_from_slice(generation_scores);
+            scores.truncate(episodes);
+        }
+        drop(generation_scores);
+        shared_resources.generation.fetch_add(1, Ordering::SeqCst);
+        println!(
+            "Generation {} of {total_generations} complete, {} of {episodes} episodes played",
+            generation + 1,
+            episode_scores.first().map_or(0, Vec::len)
+        );
+    }
+    episode_scores
+}
+/// Replaces every AI the configured selection strategy does not keep with a mutated child of
+/// parents it selects, crossed over when the config enables crossover.
+pub fn evolve_ais(shared_resources: &SharedResources) {
+    let generation = shared_resources.generation.load(Ordering::SeqCst);
+    let max_tweak_change = Positive::<f32>::new(shared_resources.config.max_tweak_change)
+        .expect("Config validation guarantees a positive max_tweak_change");
+    let strategy = shared_resources.config.selection.strategy();
+
+    let ai_scores = { lock_with_error!(&shared_resources.ai_scores).clone() };
+    let ranked_scores = RankedScores::new(&ai_scores);
+    let mut is_survivor = vec![false; ai_scores.len()];
+    for survivor in strategy.survivors(&ranked_scores).iter() {
+        is_survivor[*survivor] = true;
+    }
+    {
+        let direction_ais = &mut lock_with_error!(shared_resources.direction_ais);
+        let shooting_ais = &mut lock_with_error!(shared_resources.shooting_ais);
+        let direction_parents = direction_ais.clone();
+        let shooting_parents = shooting_ais.clone();
+        for ai_index in (0..ai_scores.len()).filter(|ai_index| !is_survivor[*ai_index]) {
+            let mut rng = stream_rng(
+                shared_resources.seed,
+                RngStream::Mutation,
+                generation,
+                ai_index,
+            );
+            let parent = strategy.select_parent(&ranked_scores, &mut rng);
+            let other_parent = strategy.select_parent(&ranked_scores, &mut rng);
+            direction_ais[ai_index] = new_child(
+                &direction_parents[parent],
+                &direction_parents[other_parent],
+                shared_resources.config.crossover,
+                &mut rng,
+            );
+            direction_ais[ai_index].tweak_continuous(max_tweak_change, &mut rng);
+            shooting_ais[ai_index] = new_child(
+                &shooting_parents[parent],
+                &shooting_parents[other_parent],
+                shared_resources.config.crossover,
+                &mut rng,
+            );
+            shooting_ais[ai_index].tweak_continuous(max_tweak_change, &mut rng);
+        }
+    }
+}
+fn new_child(
+    parent: &NeuralNetwork,
+    other_parent: &NeuralNetwork,
+    crossover: Option<Crossover>,
+    rng: &mut StdRng,
+) -> NeuralNetwork {
+    let Some(crossover) = crossover else {
+        return parent.clone();
+    };
+    parent
+        .crossover(other_parent, crossover, rng)
+        .unwrap_or_else(|error| {
+            eprintln!("Crossover failed, cloning the first parent instead: {error}");
+            parent.clone()
+        })
+}
@@ -0,0 +1,92 @@
+use std::{
+    num::NonZero,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::new_arc_mutex;
+
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed set of threads that is created once and reused for every generation, so the
+/// population size does not decide how many OS threads run.
+pub struct WorkerPool {
+    size: NonZero<usize>,
+    sender: Mutex<Option<Sender<Job>>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: NonZero<usize>) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = new_arc_mutex!(receiver);
+        let workers = (0..size.into())
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || run_worker(&receiver))
+            })
+            .collect();
+        Self {
+            size,
+            sender: Mutex::new(Some(sender)),
+            workers: Mutex::new(workers),
+        }
+    }
+    /// One worker per available core, or a single worker if that cannot be determined.
+    pub fn default_size() -> NonZero<usize> {
+        thread::available_parallelism().unwrap_or(NonZero::<usize>::MIN)
+    }
+    pub fn size(&self) -> NonZero<usize> {
+        self.size
+    }
+    /// Runs every job on the pool and waits until all of them have finished, panicking if any
+    /// of them panicked.
+    pub fn run_all(&self, jobs: Vec<Job>) {
+        let total_jobs = jobs.len();
+        let (done_sender, done_receiver) = mpsc::channel::<bool>();
+        {
+            let sender = lock_with_error!(self.sender);
+            let sender = sender.as_ref().expect("Worker pool is shut down");
+            for job in jobs {
+                let done_sender = done_sender.clone();
+                sender
+                    .send(Box::new(move || {
+                        let succeeded = panic::catch_unwind(AssertUnwindSafe(job)).is_ok();
+                        let _ = done_sender.send(succeeded);
+                    }))
+                    .expect("Worker pool threads have stopped");
+            }
+        }
+        drop(done_sender);
+
+        let succeeded = done_receiver
+            .iter()
+            .take(total_jobs)
+            .filter(|succeeded| *succeeded)
+            .count();
+        assert_eq!(succeeded, total_jobs, "AI thread panicked");
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        lock_with_error!(self.sender).take();
+        for worker in lock_with_error!(self.workers).drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = { lock_with_error!(receiver).recv() };
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
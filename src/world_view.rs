@@ -0,0 +1,61 @@
+//! Drawing and pacing shared by every window that shows a cannon's world: the training display,
+//! the play mode and the replay viewer.
+
+use raylib::prelude::RaylibDrawHandle;
+
+use crate::entity::{Bullet, Cannon, Enemy, Sprite};
+
+/// Draws the cannon, then its bullets and the enemies on top of it.
+pub fn draw_world(
+    d: &mut RaylibDrawHandle<'_>,
+    cannon: &Cannon,
+    bullets: &[Bullet],
+    enemies: &[Enemy],
+) {
+    cannon.draw(d);
+    for bullet in bullets {
+        bullet.draw(d);
+    }
+    for enemy in enemies {
+        enemy.draw(d);
+    }
+}
+
+/// Turns the time between frames into whole fixed simulation steps. At most
+/// `max_steps_per_frame` steps are played per frame and the rest is dropped, so that a stalled
+/// window does not fast-forward.
+pub struct FixedStepClock {
+    delta_time: f32,
+    max_steps_per_frame: usize,
+    unsimulated_time: f32,
+}
+
+impl FixedStepClock {
+    pub fn new(delta_time: f32, max_steps_per_frame: usize) -> Self {
+        Self {
+            delta_time,
+            max_steps_per_frame,
+            unsimulated_time: 0.0,
+        }
+    }
+    /// Adds `frame_time` and calls `step` once for every step that is due, until it returns
+    /// `false` because there is nothing left to play.
+    pub fn advance(&mut self, frame_time: f32, mut step: impl FnMut() -> bool) {
+        self.unsimulated_time += frame_time;
+        let mut steps = 0;
+        while self.unsimulated_time >= self.delta_time && steps < self.max_steps_per_frame {
+            if !step() {
+                return;
+            }
+            self.unsimulated_time -= self.delta_time;
+            steps += 1;
+        }
+        if steps == self.max_steps_per_frame {
+            self.unsimulated_time = 0.0;
+        }
+    }
+    /// Forgets the time that has not been played yet, e.g. after a pause or a jump.
+    pub fn reset(&mut self) {
+        self.unsimulated_time = 0.0;
+    }
+}
@@ -0,0 +1,93 @@
+use cannon_ai::{
+    initializer::Initializer,
+    neural_network::{Activation, NeuralNetwork},
+};
+use na::DVector;
+use rand::{rngs::StdRng, SeedableRng};
+
+fn new_network() -> NeuralNetwork {
+    NeuralNetwork::new_random(
+        &[4, 3, 3],
+        Initializer::Xavier,
+        &mut StdRng::seed_from_u64(2),
+    )
+    .unwrap()
+}
+
+#[test]
+fn networks_default_to_tanh() {
+    assert_eq!(
+        new_network().activations(),
+        &[Activation::Tanh, Activation::Tanh]
+    );
+}
+
+#[test]
+fn json_without_activations_loads_as_tanh() {
+    let network = new_network()
+        .with_activations(&[Activation::Relu, Activation::Linear])
+        .unwrap();
+    let mut json = serde_json::to_value(&network).unwrap();
+    json.as_object_mut().unwrap().remove("activations");
+
+    let loaded: NeuralNetwork = serde_json::from_value(json).unwrap();
+    assert_eq!(loaded.activations(), &[Activation::Tanh, Activation::Tanh]);
+}
+
+#[test]
+fn activations_survive_a_round_trip() {
+    let network = new_network()
+        .with_activations(&[Activation::LeakyRelu, Activation::Softmax])
+        .unwrap();
+    let json = serde_json::to_string(&network).unwrap();
+    assert!(json.contains("\"leaky_relu\""));
+
+    let loaded: NeuralNetwork = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.activations(), network.activations());
+    let input = DVector::from_vec(vec![0.1, 0.2, -0.3, 0.4]);
+    assert_eq!(loaded.run(&input), network.run(&input));
+}
+
+#[test]
+fn softmax_output_is_a_distribution() {
+    let network = new_network()
+        .with_activations(&[Activation::Relu, Activation::Softmax])
+        .unwrap();
+    let output = network
+        .run(&DVector::from_vec(vec![1.0, -2.0, 3.0, 0.5]))
+        .unwrap();
+    assert!((output.sum() - 1.0).abs() < 1e-5);
+    assert!(output.iter().all(|probability| *probability >= 0.0));
+}
+
+#[test]
+fn rejects_misplaced_softmax_and_wrong_counts() {
+    assert!(new_network()
+        .with_activations(&[Activation::Softmax, Activation::Linear])
+        .is_err());
+    assert!(new_network().with_activations(&[Activation::Tanh]).is_err());
+
+    let mut json = serde_json::to_value(new_network()).unwrap();
+    json["activations"] = serde_json::json!(["tanh"]);
+    assert!(serde_json::from_value::<NeuralNetwork>(json).is_err());
+}
+
+#[test]
+fn layer_outputs_end_with_the_network_output() {
+    let network = new_network()
+        .with_activations(&[Activation::Relu, Activation::Softmax])
+        .unwrap();
+    let input = DVector::from_vec(vec![1.0, -2.0, 3.0, 0.5]);
+    let outputs = network.layer_outputs(&input).unwrap();
+    assert_eq!(
+        outputs
+            .iter()
+            .map(|values| values.len())
+            .collect::<Vec<usize>>(),
+        [4, 3, 3]
+    );
+    assert_eq!(outputs[0], input);
+    assert!(outputs[1].iter().all(|value| *value >= 0.0));
+    assert_eq!(outputs[2], network.run(&input).unwrap());
+    assert!(network.layer_outputs(&DVector::zeros(3)).is_err());
+}
@@ -0,0 +1,135 @@
+use std::num::NonZero;
+
+use cannon_ai::{
+    initializer::Initializer,
+    neural_network::{Activation, Loss, NeuralNetwork},
+    optimizer::{Adam, Optimizer, Sgd},
+};
+use na::DVector;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const EPSILON: f32 = 1e-2;
+const TOLERANCE: f32 = 2e-2;
+
+fn new_network(seed: u64) -> NeuralNetwork {
+    let mut rng = StdRng::seed_from_u64(seed);
+    NeuralNetwork::new_random(
+        &[3, 4, 2],
+        Initializer::UniformSymmetric { limit: 0.5 },
+        &mut rng,
+    )
+    .unwrap()
+}
+
+fn assert_matches_finite_differences(activations: &[Activation], loss: Loss, target: DVector<f32>) {
+    let mut network = new_network(11).with_activations(activations).unwrap();
+    let input = DVector::from_vec(vec![0.3, -0.7, 0.5]);
+    let (_, gradients) = network.backward(&input, &target, loss).unwrap();
+    let gradients = gradients
+        .as_slices()
+        .iter()
+        .map(|gradient| gradient.to_vec())
+        .collect::<Vec<_>>();
+    let samples = [(input, target)];
+
+    for (tensor, tensor_gradients) in gradients.iter().enumerate() {
+        for (element, &analytical) in tensor_gradients.iter().enumerate() {
+            let original = network.parameters_mut()[tensor][element];
+            network.parameters_mut()[tensor][element] = original + EPSILON;
+            let loss_above = network.loss(&samples, loss).unwrap();
+            network.parameters_mut()[tensor][element] = original - EPSILON;
+            let loss_below = network.loss(&samples, loss).unwrap();
+            network.parameters_mut()[tensor][element] = original;
+
+            let numerical = (loss_above - loss_below) / (2.0 * EPSILON);
+            assert!(
+                (numerical - analytical).abs() <= TOLERANCE * (1.0 + numerical.abs()),
+                "tensor {tensor} element {element}: numerical {numerical}, analytical {analytical}"
+            );
+        }
+    }
+}
+
+#[test]
+fn mean_squared_error_gradients_match_finite_differences() {
+    assert_matches_finite_differences(
+        &[Activation::Tanh, Activation::Tanh],
+        Loss::MeanSquaredError,
+        DVector::from_vec(vec![0.25, -0.5]),
+    );
+}
+
+#[test]
+fn softmax_output_gradients_match_finite_differences() {
+    assert_matches_finite_differences(
+        &[Activation::Sigmoid, Activation::Softmax],
+        Loss::MeanSquaredError,
+        DVector::from_vec(vec![0.0, 1.0]),
+    );
+}
+
+#[test]
+fn softmax_cross_entropy_gradients_match_finite_differences() {
+    assert_matches_finite_differences(
+        &[Activation::LeakyRelu, Activation::Linear],
+        Loss::SoftmaxCrossEntropy,
+        DVector::from_vec(vec![0.0, 1.0]),
+    );
+}
+
+#[test]
+fn backward_rejects_wrong_target_size() {
+    let network = new_network(1);
+    let input = DVector::from_vec(vec![0.0; 3]);
+    let target = DVector::from_vec(vec![0.0; 3]);
+    assert!(network
+        .backward(&input, &target, Loss::MeanSquaredError)
+        .is_err());
+}
+
+fn samples() -> Vec<(DVector<f32>, DVector<f32>)> {
+    let mut rng = StdRng::seed_from_u64(5);
+    (0..32)
+        .map(|_| {
+            let input = DVector::from_fn(3, |_, _| rng.gen_range(-1.0..1.0));
+            let target = DVector::from_vec(vec![
+                0.5 * (input[0] - input[1]),
+                0.5 * (input[1] + input[2]),
+            ]);
+            (input, target)
+        })
+        .collect()
+}
+
+fn assert_training_reduces_loss(mut optimizer: impl Optimizer) {
+    let samples = samples();
+    let mut network = new_network(3);
+    let mut rng = StdRng::seed_from_u64(9);
+    let initial_loss = network.loss(&samples, Loss::MeanSquaredError).unwrap();
+    for _ in 0..200 {
+        network
+            .train_epoch(
+                &samples,
+                NonZero::new(8).unwrap(),
+                Loss::MeanSquaredError,
+                &mut optimizer,
+                &mut rng,
+            )
+            .unwrap();
+    }
+    let final_loss = network.loss(&samples, Loss::MeanSquaredError).unwrap();
+    assert!(
+        final_loss < initial_loss * 0.1,
+        "loss went from {initial_loss} to {final_loss}"
+    );
+}
+
+#[test]
+fn sgd_with_momentum_reduces_loss() {
+    assert_training_reduces_loss(Sgd::new(0.05, 0.9));
+}
+
+#[test]
+fn adam_reduces_loss() {
+    assert_training_reduces_loss(Adam::new(0.01));
+}
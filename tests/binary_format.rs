@@ -0,0 +1,185 @@
+use std::{env, fs, io::Write};
+
+use cannon_ai::{
+    initializer::Initializer,
+    neural_network::{Activation, NeuralNetwork},
+    storage::{self, LoadError, NetworkFormat, BINARY_MAGIC, BINARY_VERSION},
+};
+use flate2::{write::ZlibEncoder, Compression};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn population() -> Box<[NeuralNetwork]> {
+    let mut rng = StdRng::seed_from_u64(21);
+    let mut networks = (0..4)
+        .map(|_| NeuralNetwork::new_random_unchecked(&[20, 10, 3], Initializer::Xavier, &mut rng))
+        .collect::<Vec<NeuralNetwork>>();
+    networks.push(
+        NeuralNetwork::new_random_unchecked(&[5, 4, 4, 2], Initializer::He, &mut rng)
+            .with_activations(&[Activation::LeakyRelu, Activation::Relu, Activation::Softmax])
+            .unwrap(),
+    );
+    // Values that a decimal round trip could lose.
+    let parameters = networks[0].parameters_mut();
+    let special = [
+        -0.0,
+        f32::MIN_POSITIVE / 3.0,
+        f32::MAX,
+        0.1 + 0.2,
+        1.0 / 3.0,
+    ];
+    for (parameter, value) in parameters[0].iter_mut().zip(special) {
+        *parameter = value;
+    }
+    networks.into_boxed_slice()
+}
+fn bits(networks: &[NeuralNetwork]) -> Vec<u32> {
+    networks
+        .iter()
+        .flat_map(|network| {
+            network
+                .weights()
+                .iter()
+                .flat_map(|weight| {
+                    weight
+                        .iter()
+                        .map(|value| value.to_bits())
+                        .collect::<Vec<u32>>()
+                })
+                .chain(network.biases().iter().flat_map(|bias| {
+                    bias.iter()
+                        .map(|value| value.to_bits())
+                        .collect::<Vec<u32>>()
+                }))
+                .collect::<Vec<u32>>()
+        })
+        .collect()
+}
+
+#[test]
+fn binary_round_trips_are_bit_exact() {
+    let networks = population();
+    for format in [NetworkFormat::Binary, NetworkFormat::CompressedBinary] {
+        let bytes = storage::encode_networks(&networks, format).unwrap();
+        let decoded = storage::decode_networks(&bytes).unwrap();
+        assert_eq!(bits(&decoded), bits(&networks));
+        for (decoded, network) in decoded.iter().zip(networks.iter()) {
+            assert_eq!(decoded.layer_sizes(), network.layer_sizes());
+            assert_eq!(decoded.activations(), network.activations());
+        }
+    }
+}
+
+#[test]
+fn writes_the_documented_header() {
+    let networks = population();
+    let bytes = storage::encode_networks(&networks, NetworkFormat::Binary).unwrap();
+    assert_eq!(&bytes[..4], BINARY_MAGIC);
+    assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), BINARY_VERSION);
+    assert_eq!(bytes[6], 0);
+    assert_eq!(
+        u32::from_le_bytes(bytes[7..11].try_into().unwrap()),
+        networks.len() as u32
+    );
+    let first_input_size = u32::from_le_bytes(bytes[11..15].try_into().unwrap());
+    assert_eq!(first_input_size, 20);
+
+    let compressed = storage::encode_networks(&networks, NetworkFormat::CompressedBinary).unwrap();
+    assert_eq!(compressed[6], 1);
+    let json = storage::encode_networks(&networks, NetworkFormat::Json).unwrap();
+    assert!(bytes.len() * 3 < json.len());
+}
+
+#[test]
+fn detects_the_format_on_load() {
+    let networks = population();
+    let directory = env::temp_dir();
+    for (name, format) in [
+        ("json", NetworkFormat::Json),
+        ("binary", NetworkFormat::Binary),
+        ("compressed", NetworkFormat::CompressedBinary),
+    ] {
+        // The extension does not decide the format.
+        let path = directory.join(format!("cannon_ai_detect_{name}.dat"));
+        storage::save_networks(&path, &networks, format).unwrap();
+        let loaded = storage::load_networks(&path).unwrap().unwrap();
+        assert_eq!(bits(&loaded), bits(&networks));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn rejects_corrupt_binary_files() {
+    let networks = population();
+    let bytes = storage::encode_networks(&networks, NetworkFormat::Binary).unwrap();
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(storage::decode_networks(truncated).is_err());
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(storage::decode_networks(&trailing).is_err());
+
+    let mut future_version = bytes.clone();
+    future_version[4] = 2;
+    assert!(storage::decode_networks(&future_version)
+        .err()
+        .unwrap()
+        .contains("version 2"));
+
+    let mut unknown_activation = bytes.clone();
+    unknown_activation[23] = 200;
+    assert!(storage::decode_networks(&unknown_activation)
+        .err()
+        .unwrap()
+        .contains("activation code 200"));
+
+    let mut huge_count = bytes.clone();
+    huge_count[7..11].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(storage::decode_networks(&huge_count).is_err());
+
+    let compressed = storage::encode_networks(&networks, NetworkFormat::CompressedBinary).unwrap();
+    assert!(storage::decode_networks(&compressed[..compressed.len() / 2]).is_err());
+}
+
+/// Builds a compressed network file around an arbitrary body.
+fn compressed_file(body: &[u8]) -> Vec<u8> {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+    bytes.push(1);
+    let mut encoder = ZlibEncoder::new(bytes, Compression::best());
+    encoder.write_all(body).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn compressed_bodies_stop_inflating_at_their_declared_shapes() {
+    let networks = population();
+    let body = storage::encode_networks(&networks, NetworkFormat::Binary).unwrap()[7..].to_vec();
+
+    // Megabytes of padding that compress to almost nothing are never inflated.
+    let mut padded = body.clone();
+    padded.resize(body.len() + (16 << 20), 0);
+    let bomb = compressed_file(&padded);
+    assert!(bomb.len() < 50_000);
+    assert!(storage::decode_networks(&bomb)
+        .err()
+        .unwrap()
+        .contains("after the last network"));
+
+    // A layer claiming billions of weights fails once the real data runs out.
+    let mut huge_layer = vec![];
+    for value in [1u32, 4, 2, 1] {
+        huge_layer.extend_from_slice(&value.to_le_bytes());
+    }
+    huge_layer.push(0);
+    for value in [u32::MAX, 4] {
+        huge_layer.extend_from_slice(&value.to_le_bytes());
+    }
+    huge_layer.extend_from_slice(&[0; 64]);
+    let path = env::temp_dir().join("cannon_ai_huge_layer.bin");
+    fs::write(&path, compressed_file(&huge_layer)).unwrap();
+    let error = storage::load_networks(&path).err().unwrap();
+    assert!(matches!(error, LoadError::Parse { .. }));
+    assert!(error.to_string().contains("Unexpected end of file"));
+    fs::remove_file(&path).unwrap();
+}
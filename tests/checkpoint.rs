@@ -0,0 +1,184 @@
+use std::{
+    env, fs,
+    num::NonZero,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::Duration,
+};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    lock_with_error,
+    multi_threading::{Checkpoint, PopulationFiles, SharedResources},
+    trainer::{self, SimulationOptions},
+};
+
+fn files(name: &str) -> PopulationFiles {
+    PopulationFiles::in_directory(&env::temp_dir(), name)
+}
+fn remove_files(name: &str) {
+    let files = files(name);
+    for path in [
+        files.direction_path,
+        files.shooting_path,
+        files.checkpoint_path,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let _ = fs::remove_file(path);
+    }
+}
+fn checkpoint_path(name: &str) -> PathBuf {
+    files(name).checkpoint_path.unwrap()
+}
+fn new_shared_resources(name: &str) -> SharedResources {
+    remove_files(name);
+    let config = ExperimentConfig {
+        training_time: 2.0,
+        ..ExperimentConfig::default()
+    };
+    SharedResources::new(config, 11, NonZero::new(6), NonZero::new(2), files(name)).unwrap()
+}
+fn train(shared_resources: &SharedResources, max_generations: usize, checkpoint_every: usize) {
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    shared_resources.is_running.store(true, Ordering::SeqCst);
+    trainer::run_simulation(
+        shared_resources.clone(),
+        SimulationOptions {
+            startup_delay: Duration::ZERO,
+            max_generations: Some(max_generations),
+            evolve: true,
+            metrics_log: None,
+            checkpoint_every: NonZero::new(checkpoint_every),
+            replay_dir: None,
+        },
+    )
+    .join()
+    .unwrap();
+}
+fn networks_json(shared_resources: &SharedResources) -> String {
+    serde_json::to_string(&(
+        &*lock_with_error!(shared_resources.direction_ais),
+        &*lock_with_error!(shared_resources.shooting_ais),
+    ))
+    .unwrap()
+}
+
+#[test]
+fn resuming_matches_an_uninterrupted_run() {
+    let uninterrupted = new_shared_resources("checkpoint_uninterrupted");
+    train(&uninterrupted, 4, 0);
+
+    let interrupted = new_shared_resources("checkpoint_interrupted");
+    train(&interrupted, 2, 0);
+    interrupted.save_ais().unwrap();
+
+    let checkpoint = Checkpoint::load(&checkpoint_path("checkpoint_interrupted")).unwrap();
+    assert_eq!(checkpoint.generation, 2);
+    assert_eq!(checkpoint.fitness_history.len(), 2);
+    let resumed = SharedResources::from_checkpoint(
+        checkpoint,
+        None,
+        NonZero::new(2),
+        files("checkpoint_interrupted"),
+    )
+    .unwrap();
+    train(&resumed, 4, 0);
+
+    assert_eq!(resumed.generation.load(Ordering::SeqCst), 4);
+    assert_eq!(networks_json(&resumed), networks_json(&uninterrupted));
+    assert_eq!(
+        *lock_with_error!(resumed.fitness_history),
+        *lock_with_error!(uninterrupted.fitness_history)
+    );
+    remove_files("checkpoint_uninterrupted");
+    remove_files("checkpoint_interrupted");
+}
+
+#[test]
+fn saves_periodically_without_leaving_temporary_files() {
+    let shared_resources = new_shared_resources("checkpoint_periodic");
+    train(&shared_resources, 3, 2);
+
+    let path = checkpoint_path("checkpoint_periodic");
+    let checkpoint = Checkpoint::load(&path).unwrap();
+    assert_eq!(checkpoint.generation, 2);
+    assert_eq!(checkpoint.seed, 11);
+    assert_eq!(checkpoint.direction_ais.len(), 6);
+    let temporary_path = Path::new(&format!("{}.tmp", path.display())).to_path_buf();
+    assert!(!temporary_path.exists());
+    remove_files("checkpoint_periodic");
+}
+
+#[test]
+fn rejects_a_checkpoint_of_another_population_size() {
+    let shared_resources = new_shared_resources("checkpoint_size");
+    let result = SharedResources::from_checkpoint(
+        shared_resources.checkpoint(),
+        NonZero::new(7),
+        NonZero::new(1),
+        files("checkpoint_size"),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn checkpoints_without_history_start_at_generation_zero() {
+    let shared_resources = new_shared_resources("checkpoint_legacy");
+    let checkpoint = shared_resources.checkpoint();
+    let legacy_json = serde_json::json!({
+        "config": checkpoint.config,
+        "seed": checkpoint.seed,
+        "direction_ais": checkpoint.direction_ais,
+        "shooting_ais": checkpoint.shooting_ais,
+    })
+    .to_string();
+    let checkpoint = serde_json::from_str::<Checkpoint>(&legacy_json).unwrap();
+    assert_eq!(checkpoint.generation, 0);
+    assert!(checkpoint.fitness_history.is_empty());
+    assert_eq!(checkpoint.wall_time_secs, 0.0);
+}
+
+#[test]
+fn wall_time_continues_across_resumes() {
+    let shared_resources = new_shared_resources("checkpoint_wall_time");
+    let mut checkpoint = shared_resources.checkpoint();
+    checkpoint.wall_time_secs = 1000.0;
+    let resumed = SharedResources::from_checkpoint(
+        checkpoint,
+        None,
+        NonZero::new(2),
+        files("checkpoint_wall_time"),
+    )
+    .unwrap();
+    train(&resumed, 1, 0);
+
+    let wall_time = *lock_with_error!(resumed.wall_time);
+    assert!(wall_time > Duration::from_secs(1000), "{wall_time:?}");
+    assert_eq!(resumed.checkpoint().wall_time_secs, wall_time.as_secs_f64());
+
+    let mut invalid = resumed.checkpoint();
+    invalid.wall_time_secs = -1.0;
+    assert!(SharedResources::from_checkpoint(
+        invalid,
+        None,
+        NonZero::new(1),
+        files("checkpoint_wall_time")
+    )
+    .is_err());
+    remove_files("checkpoint_wall_time");
+}
+
+#[test]
+fn rejects_a_checkpoint_with_an_unusable_config() {
+    let shared_resources = new_shared_resources("checkpoint_config");
+    let mut checkpoint = shared_resources.checkpoint();
+    checkpoint.config.total_view_rays = 0;
+    let path = checkpoint_path("checkpoint_config");
+    fs::write(&path, serde_json::to_string(&checkpoint).unwrap()).unwrap();
+
+    let error = Checkpoint::load(&path).err().unwrap();
+    assert!(error.to_string().contains("total_view_rays"), "{error}");
+    remove_files("checkpoint_config");
+}
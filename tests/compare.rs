@@ -0,0 +1,240 @@
+use std::{env, fs, num::NonZero, sync::Arc};
+
+use cannon_ai::{
+    compare::{self, CompareOptions, Contestant, Population},
+    config::ExperimentConfig,
+    entity::Point,
+    environment::StepInfo,
+    initializer::Initializer,
+    neural_network::NeuralNetwork,
+    storage::{self, NetworkFormat},
+    worker_pool::WorkerPool,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn config() -> Arc<ExperimentConfig> {
+    Arc::new(ExperimentConfig {
+        training_time: 3.0,
+        ..ExperimentConfig::default()
+    })
+}
+fn dimensions() -> Point {
+    Point {
+        x: 1000.0,
+        y: 750.0,
+    }
+}
+fn random_population(
+    name: &str,
+    total_ais: usize,
+    total_view_rays: usize,
+    seed: u64,
+) -> Population {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut networks = |outputs| {
+        (0..total_ais)
+            .map(|_| {
+                NeuralNetwork::new_random_unchecked(
+                    &[total_view_rays, 8, outputs],
+                    Initializer::Xavier,
+                    &mut rng,
+                )
+            })
+            .collect()
+    };
+    Population {
+        name: name.to_string(),
+        direction_ais: networks(3),
+        shooting_ais: networks(2),
+    }
+}
+fn options(episodes: usize, every_ai: bool) -> CompareOptions {
+    CompareOptions {
+        episodes,
+        qualifying_episodes: 2,
+        every_ai,
+    }
+}
+fn episode(score: f32, spawned: usize, killed: usize, fired: usize) -> StepInfo {
+    StepInfo {
+        score,
+        enemies_spawned: spawned,
+        enemies_killed: killed,
+        bullets_fired: fired,
+        ..StepInfo::default()
+    }
+}
+
+#[test]
+fn summarizes_a_contestants_episodes() {
+    let contestant = Contestant {
+        name: "a".to_string(),
+        population: 0,
+        ai_index: 0,
+        episodes: vec![
+            episode(4.0, 10, 4, 10),
+            episode(-2.0, 10, 0, 0),
+            episode(10.0, 10, 6, 10),
+            episode(0.0, 10, 2, 20),
+        ],
+    };
+    assert_eq!(contestant.mean_score(), 3.0);
+    // The sample standard deviation is sqrt(28), with t = 3.182 for 3 degrees of freedom.
+    let expected = 3.182 * 28.0_f32.sqrt() / 2.0;
+    assert!((contestant.confidence_interval() - expected).abs() < 1e-4);
+    assert_eq!(contestant.kill_rate(), 0.3);
+    assert_eq!(contestant.accuracy(), 0.3);
+
+    let idle = Contestant {
+        episodes: vec![episode(0.0, 0, 0, 0); 40],
+        ..contestant
+    };
+    assert_eq!(idle.confidence_interval(), 0.0);
+    assert_eq!(idle.kill_rate(), 0.0);
+    assert_eq!(idle.accuracy(), 0.0);
+}
+
+#[test]
+fn contestants_play_the_same_episodes() {
+    let config = config();
+    let worker_pool = WorkerPool::new(NonZero::new(3).unwrap());
+    let populations = [
+        random_population("first", 3, config.total_view_rays, 1),
+        random_population("second", 4, config.total_view_rays, 2),
+        random_population("first", 3, config.total_view_rays, 1),
+    ];
+    let contestants = compare::compare(
+        &config,
+        &dimensions(),
+        7,
+        &populations,
+        &options(3, false),
+        &worker_pool,
+    )
+    .unwrap();
+    assert_eq!(contestants.len(), 3);
+    assert!(contestants
+        .windows(2)
+        .all(|pair| pair[0].mean_score() >= pair[1].mean_score()));
+    assert!(contestants
+        .iter()
+        .all(|contestant| contestant.episodes.len() == 3));
+
+    // Identical populations pick the same champion and get identical results.
+    let copies = contestants
+        .iter()
+        .filter(|contestant| contestant.population != 1)
+        .collect::<Vec<&Contestant>>();
+    assert_eq!(copies[0].ai_index, copies[1].ai_index);
+    assert_eq!(copies[0].episodes, copies[1].episodes);
+    assert_eq!(copies[0].name, format!("first AI {}", copies[0].ai_index));
+
+    let again = compare::compare(
+        &config,
+        &dimensions(),
+        7,
+        &populations,
+        &options(3, false),
+        &worker_pool,
+    )
+    .unwrap();
+    assert_eq!(again, contestants);
+}
+
+#[test]
+fn can_compare_every_ai() {
+    let config = config();
+    let worker_pool = WorkerPool::new(NonZero::new(2).unwrap());
+    let populations = [
+        random_population("first", 2, config.total_view_rays, 3),
+        random_population("second", 3, config.total_view_rays, 4),
+    ];
+    let contestants = compare::compare(
+        &config,
+        &dimensions(),
+        1,
+        &populations,
+        &options(2, true),
+        &worker_pool,
+    )
+    .unwrap();
+    let mut entrants = contestants
+        .iter()
+        .map(|contestant| (contestant.population, contestant.ai_index))
+        .collect::<Vec<(usize, usize)>>();
+    entrants.sort();
+    assert_eq!(entrants, [(0, 0), (0, 1), (1, 0), (1, 1), (1, 2)]);
+
+    let table = compare::comparison_table(&contestants);
+    assert_eq!(table.lines().count(), 6);
+    assert!(table.lines().next().unwrap().contains("Kill rate"));
+    assert!(table.lines().nth(1).unwrap().starts_with("   1  "));
+}
+
+#[test]
+fn rejects_comparisons_that_cannot_run() {
+    let config = config();
+    let worker_pool = WorkerPool::new(NonZero::new(1).unwrap());
+    let total_view_rays = config.total_view_rays;
+    let run = |populations: &[Population], episodes| {
+        compare::compare(
+            &config,
+            &dimensions(),
+            0,
+            populations,
+            &options(episodes, true),
+            &worker_pool,
+        )
+        .unwrap_err()
+    };
+    let populations = [random_population("only", 1, total_view_rays, 0)];
+    assert!(run(&populations, 5).contains("two populations"));
+    let populations = [
+        random_population("first", 1, total_view_rays, 0),
+        random_population("second", 1, total_view_rays, 1),
+    ];
+    assert!(run(&populations, 1).contains("two episodes"));
+    let populations = [
+        random_population("first", 1, total_view_rays, 0),
+        random_population("wider", 1, total_view_rays + 1, 1),
+    ];
+    assert!(run(&populations, 5).contains("wider"));
+}
+
+#[test]
+fn loads_populations_from_network_files() {
+    let directory = env::temp_dir().join("cannon_ai_compare");
+    fs::create_dir_all(&directory).unwrap();
+    let direction_path = directory.join("direction.bin");
+    let shooting_path = directory.join("shooting.json");
+    let population = random_population("saved", 2, 4, 5);
+    storage::save_networks(
+        &direction_path,
+        &population.direction_ais,
+        NetworkFormat::CompressedBinary,
+    )
+    .unwrap();
+    storage::save_networks(
+        &shooting_path,
+        &population.shooting_ais,
+        NetworkFormat::Json,
+    )
+    .unwrap();
+    let loaded = Population::from_files(&direction_path, &shooting_path).unwrap();
+    assert_eq!(loaded.name, direction_path.display().to_string());
+    assert_eq!(loaded.direction_ais.len(), 2);
+    assert_eq!(loaded.shooting_ais.len(), 2);
+
+    storage::save_networks(
+        &shooting_path,
+        &population.shooting_ais[..1],
+        NetworkFormat::Json,
+    )
+    .unwrap();
+    let error = Population::from_files(&direction_path, &shooting_path)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("holds 1 AIs but 2 are needed"));
+    assert!(Population::from_files(&directory.join("missing.json"), &shooting_path).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,139 @@
+use std::{env, fs, io, path::PathBuf};
+
+use cannon_ai::config::ExperimentConfig;
+
+fn write_config(file_name: &str, contents: &str) -> PathBuf {
+    let directory = env::temp_dir().join("cannon_ai_config");
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(file_name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn defaults_match_the_original_constants() {
+    let config = ExperimentConfig::default();
+    assert_eq!(config.gun_rotate_velocity, 0.75);
+    assert_eq!(config.bullet_speed, 150.0);
+    assert_eq!(config.bullet_cooldown, 1.0);
+    assert_eq!(config.enemy_cooldown, 3.0);
+    assert_eq!(config.enemy_speed, 45.0);
+    assert_eq!(config.enemy_spawn_distance, 1.0);
+    assert_eq!(config.total_view_rays, 20);
+    assert_eq!(config.view_ray_length, 400.0);
+    assert_eq!(config.fast_delta_time, 0.005);
+    assert_eq!(config.training_time, 60.0);
+    assert_eq!(config.max_tweak_change, 0.05);
+
+    let sizes = &config.entity_sizes;
+    assert_eq!(sizes.cannon_radius, 50.0);
+    assert_eq!(sizes.barrel_height, 40.0);
+    assert_eq!(sizes.enemy_width, 75.0);
+    assert_eq!(sizes.enemy_height, 100.0);
+    assert_eq!(sizes.bullet_width, 15.0);
+    assert_eq!(sizes.bullet_height, 25.0);
+    assert_eq!(config.validate(), Ok(()));
+}
+
+#[test]
+fn rejects_unusable_values() {
+    let rejects = |config: ExperimentConfig, field: &str| {
+        let error = config.validate().unwrap_err();
+        assert!(error.contains(field), "{error}");
+    };
+    rejects(
+        ExperimentConfig {
+            total_view_rays: 0,
+            ..ExperimentConfig::default()
+        },
+        "total_view_rays",
+    );
+    rejects(
+        ExperimentConfig {
+            bullet_speed: -150.0,
+            ..ExperimentConfig::default()
+        },
+        "bullet_speed",
+    );
+    rejects(
+        ExperimentConfig {
+            enemy_speed: -1.0,
+            ..ExperimentConfig::default()
+        },
+        "enemy_speed",
+    );
+    rejects(
+        ExperimentConfig {
+            gun_rotate_velocity: f32::NAN,
+            ..ExperimentConfig::default()
+        },
+        "gun_rotate_velocity",
+    );
+    for fast_delta_time in [0.0, -0.005] {
+        rejects(
+            ExperimentConfig {
+                fast_delta_time,
+                ..ExperimentConfig::default()
+            },
+            "fast_delta_time",
+        );
+    }
+}
+
+#[test]
+fn loads_toml_and_json_files() {
+    let toml_path = write_config(
+        "experiment.toml",
+        "total_view_rays = 12\ntraining_time = 30.0\n\n[entity_sizes]\ncannon_radius = 40.0\n",
+    );
+    let config = ExperimentConfig::load(&toml_path).unwrap();
+    assert_eq!(config.total_view_rays, 12);
+    assert_eq!(config.training_time, 30.0);
+    assert_eq!(config.entity_sizes.cannon_radius, 40.0);
+    assert_eq!(
+        config.bullet_speed,
+        ExperimentConfig::default().bullet_speed
+    );
+
+    let json_path = write_config(
+        "experiment.json",
+        r#"{"enemy_speed": 60.0, "entity_sizes": {"bullet_width": 10.0}}"#,
+    );
+    let config = ExperimentConfig::load(&json_path).unwrap();
+    assert_eq!(config.enemy_speed, 60.0);
+    assert_eq!(config.entity_sizes.bullet_width, 10.0);
+    assert_eq!(
+        config.total_view_rays,
+        ExperimentConfig::default().total_view_rays
+    );
+}
+
+#[test]
+fn rejects_unknown_keys_and_invalid_files() {
+    for (file_name, contents) in [
+        (
+            "unknown_key.toml",
+            "total_view_rays = 12\nbullet_sped = 10.0\n",
+        ),
+        ("unknown_key.json", r#"{"bullet_sped": 10.0}"#),
+        (
+            "unknown_size.toml",
+            "[entity_sizes]\ncannon_diameter = 10.0\n",
+        ),
+    ] {
+        let error = ExperimentConfig::load(&write_config(file_name, contents)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("unknown field"), "{error}");
+    }
+
+    let error =
+        ExperimentConfig::load(&write_config("no_rays.toml", "total_view_rays = 0\n")).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("total_view_rays"), "{error}");
+
+    let missing = env::temp_dir().join("cannon_ai_config_missing.toml");
+    assert_eq!(
+        ExperimentConfig::load(&missing).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+}
@@ -0,0 +1,145 @@
+use cannon_ai::{
+    initializer::Initializer,
+    neural_network::{Crossover, NeuralNetwork},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const LAYER_SIZES: [usize; 3] = [4, 3, 2];
+
+fn constant_network(value: f32) -> NeuralNetwork {
+    let mut network = NeuralNetwork::new_random(
+        &LAYER_SIZES,
+        Initializer::Zeros,
+        &mut StdRng::seed_from_u64(0),
+    )
+    .unwrap();
+    for tensor in network.parameters_mut().iter_mut() {
+        tensor.fill(value);
+    }
+    network
+}
+
+fn genome(network: &NeuralNetwork) -> Vec<f32> {
+    let mut network = network.clone();
+    let genome = network
+        .parameters_mut()
+        .iter()
+        .flat_map(|tensor| tensor.to_vec())
+        .collect();
+    genome
+}
+
+fn switches(genome: &[f32]) -> usize {
+    genome.windows(2).filter(|pair| pair[0] != pair[1]).count()
+}
+
+fn cross(crossover: Crossover, seed: u64) -> Vec<f32> {
+    let child = constant_network(1.0)
+        .crossover(
+            &constant_network(2.0),
+            crossover,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .unwrap();
+    genome(&child)
+}
+
+#[test]
+fn uniform_mixes_both_parents() {
+    let child = cross(Crossover::Uniform, 1);
+    assert!(child.contains(&1.0) && child.contains(&2.0));
+    assert!(child.iter().all(|value| *value == 1.0 || *value == 2.0));
+}
+
+#[test]
+fn single_point_switches_parents_once() {
+    for seed in 0..20 {
+        let child = cross(Crossover::SinglePoint, seed);
+        assert_eq!(child[0], 1.0);
+        assert_eq!(*child.last().unwrap(), 2.0);
+        assert_eq!(switches(&child), 1);
+    }
+}
+
+#[test]
+fn multi_point_switches_parents_at_every_cut() {
+    for seed in 0..20 {
+        let child = cross(Crossover::MultiPoint { points: 3 }, seed);
+        assert_eq!(child[0], 1.0);
+        assert_eq!(switches(&child), 3);
+    }
+}
+
+#[test]
+fn layer_wise_keeps_layers_whole() {
+    for seed in 0..20 {
+        let mut child = constant_network(1.0)
+            .crossover(
+                &constant_network(2.0),
+                Crossover::LayerWise,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .unwrap();
+        let tensors = child.parameters_mut();
+        let (weights, biases) = tensors.split_at(LAYER_SIZES.len() - 1);
+        for (weight, bias) in weights.iter().zip(biases.iter()) {
+            let parent_value = weight[0];
+            assert!(weight
+                .iter()
+                .chain(bias.iter())
+                .all(|value| *value == parent_value));
+        }
+    }
+}
+
+#[test]
+fn same_seed_gives_the_same_child() {
+    assert_eq!(cross(Crossover::Uniform, 7), cross(Crossover::Uniform, 7));
+}
+
+#[test]
+fn incompatible_shapes_are_an_error() {
+    let other = NeuralNetwork::new_random(
+        &[4, 5, 2],
+        Initializer::Zeros,
+        &mut StdRng::seed_from_u64(0),
+    )
+    .unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+    for crossover in [
+        Crossover::Uniform,
+        Crossover::SinglePoint,
+        Crossover::MultiPoint { points: 2 },
+        Crossover::LayerWise,
+    ] {
+        assert!(constant_network(1.0)
+            .crossover(&other, crossover, &mut rng)
+            .is_err());
+    }
+}
+
+#[test]
+fn too_many_points_are_an_error() {
+    let genome_length = constant_network(0.0).parameter_count();
+    assert!(constant_network(1.0)
+        .crossover(
+            &constant_network(2.0),
+            Crossover::MultiPoint {
+                points: genome_length
+            },
+            &mut StdRng::seed_from_u64(0),
+        )
+        .is_err());
+}
+
+#[test]
+fn networks_without_parameters_cannot_be_cut() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let empty = NeuralNetwork::new_random_unchecked(&[0, 0], Initializer::Zeros, &mut rng);
+    assert_eq!(empty.parameter_count(), 0);
+    let error = empty
+        .crossover(&empty, Crossover::SinglePoint, &mut rng)
+        .err()
+        .unwrap();
+    assert!(error.contains("0 parameters"), "{error}");
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, sync::Arc};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    demonstration::{self, Demonstration},
+    entity::Point,
+    environment::{Action, CannonEnv, Rotation},
+};
+
+/// Plays one short episode holding the given keys and records it.
+fn record_episode(seed: u64, left: bool, right: bool, space: bool) -> Demonstration {
+    let config = ExperimentConfig {
+        training_time: 5.0,
+        ..ExperimentConfig::default()
+    };
+    let mut demonstration = Demonstration::new(config.clone());
+    let mut environment = CannonEnv::new(
+        Arc::new(config),
+        Point {
+            x: 1000.0,
+            y: 750.0,
+        },
+    );
+    let mut observation = environment.reset(seed);
+    demonstration.start_episode(seed);
+    let action = demonstration::keyboard_action(left, right, space);
+    loop {
+        let (next_observation, reward, done, info) = environment.step(action);
+        demonstration.record(&observation, action, reward);
+        if done {
+            demonstration.finish_episode(info.enemies_killed);
+            assert!((demonstration.episodes[0].score - info.score).abs() < 1e-3);
+            return demonstration;
+        }
+        observation = next_observation;
+    }
+}
+
+#[test]
+fn keys_map_to_actions() {
+    assert_eq!(
+        demonstration::keyboard_action(true, false, true),
+        Action {
+            rotation: Rotation::CounterClockwise,
+            shoot: true
+        }
+    );
+    assert_eq!(
+        demonstration::keyboard_action(false, true, false).rotation,
+        Rotation::Clockwise
+    );
+    assert_eq!(
+        demonstration::keyboard_action(true, true, false).rotation,
+        Rotation::Hold
+    );
+}
+
+#[test]
+fn records_only_the_shots_that_fired() {
+    let demonstration = record_episode(4, false, true, true);
+    let steps = &demonstration.episodes[0].steps;
+    assert_eq!(demonstration.total_steps(), steps.len());
+    assert!(steps.iter().all(|step| step.shoot == step.can_shoot));
+    assert!(steps.iter().any(|step| step.shoot));
+    assert!(steps.iter().any(|step| !step.shoot));
+    assert!(steps
+        .iter()
+        .all(|step| step.rotation == Rotation::Clockwise.index()));
+}
+
+#[test]
+fn samples_are_one_hot_targets_for_the_networks() {
+    let demonstration = record_episode(4, true, false, true);
+    let direction_samples = demonstration.direction_samples();
+    assert_eq!(direction_samples.len(), demonstration.total_steps());
+    for (input, target) in direction_samples.iter() {
+        assert_eq!(input.len(), demonstration.config.total_view_rays);
+        assert_eq!(target.as_slice(), [1.0, 0.0, 0.0]);
+    }
+    let shooting_samples = demonstration.shooting_samples();
+    let firing_steps = demonstration.episodes[0]
+        .steps
+        .iter()
+        .filter(|step| step.can_shoot)
+        .count();
+    assert_eq!(shooting_samples.len(), firing_steps);
+    assert!(shooting_samples
+        .iter()
+        .all(|(_, target)| target.as_slice() == [1.0, 0.0]));
+}
+
+#[test]
+fn demonstrations_round_trip_through_a_file() {
+    let mut demonstration = record_episode(9, false, false, false);
+    demonstration.start_episode(10);
+    let path = env::temp_dir().join("cannon_ai_demonstration.json");
+    demonstration.save(&path).unwrap();
+    assert_eq!(Demonstration::load(&path).unwrap(), demonstration);
+
+    fs::write(&path, "{\"episodes\": 3}").unwrap();
+    assert!(Demonstration::load(&path)
+        .unwrap_err()
+        .to_string()
+        .contains("Invalid demonstration"));
+    fs::remove_file(&path).unwrap();
+}
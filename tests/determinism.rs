@@ -0,0 +1,60 @@
+use std::{env, num::NonZero, sync::atomic::Ordering, time::Duration};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    lock_with_error,
+    multi_threading::{PopulationFiles, SharedResources},
+    trainer::{self, SimulationOptions},
+};
+
+fn train(seed: u64, name: &str) -> (String, Box<[f32]>) {
+    let config = ExperimentConfig {
+        training_time: 5.0,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = SharedResources::new(
+        config,
+        seed,
+        NonZero::new(6),
+        NonZero::new(2),
+        PopulationFiles::in_directory(&env::temp_dir(), name),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+
+    trainer::run_simulation(
+        shared_resources.clone(),
+        SimulationOptions {
+            startup_delay: Duration::ZERO,
+            max_generations: Some(3),
+            evolve: true,
+            metrics_log: None,
+            checkpoint_every: None,
+            replay_dir: None,
+        },
+    )
+    .join()
+    .unwrap();
+
+    let networks = serde_json::to_string(&(
+        &*lock_with_error!(shared_resources.direction_ais),
+        &*lock_with_error!(shared_resources.shooting_ais),
+    ))
+    .unwrap();
+    let scores = lock_with_error!(shared_resources.ai_scores).clone();
+    (networks, scores)
+}
+
+#[test]
+fn same_seed_trains_identical_population() {
+    let first = train(42, "cannon_ai_determinism_first");
+    let second = train(42, "cannon_ai_determinism_second");
+    assert_eq!(first, second);
+}
+
+#[test]
+fn different_seeds_train_different_populations() {
+    let first = train(1, "cannon_ai_determinism_seed_1");
+    let second = train(2, "cannon_ai_determinism_seed_2");
+    assert_ne!(first.0, second.0);
+}
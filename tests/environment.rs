@@ -0,0 +1,177 @@
+use std::{f32::consts::PI, ops::ControlFlow, sync::Arc};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    entity::Point,
+    environment::{self, Action, CannonEnv, Observation, Policy, Rotation},
+};
+
+fn new_env(config: ExperimentConfig) -> CannonEnv {
+    CannonEnv::new(
+        Arc::new(config),
+        Point {
+            x: 1000.0,
+            y: 750.0,
+        },
+    )
+}
+
+struct Scripted(Action);
+
+impl Policy for Scripted {
+    fn act(&mut self, _observation: &Observation) -> Action {
+        self.0
+    }
+}
+
+#[test]
+fn reset_matches_declared_observation_space() {
+    let mut env = new_env(ExperimentConfig::default());
+    let observation = env.reset(7);
+    let space = env.observation_space();
+    assert_eq!(observation.rays.len(), space.total_rays);
+    assert!(observation
+        .rays
+        .iter()
+        .all(|ray| (space.ray_low..=space.ray_high).contains(ray)));
+    assert_eq!(env.action_space().rotations, Rotation::ALL.len());
+}
+
+#[test]
+fn first_step_spawns_an_enemy_and_rotation_costs_score() {
+    let config = ExperimentConfig::default();
+    let mut env = new_env(config.clone());
+    env.reset(7);
+    let (_, reward, done, info) = env.step(Action {
+        rotation: Rotation::Clockwise,
+        shoot: false,
+    });
+    assert!(!done);
+    assert_eq!(env.enemies().len(), 1);
+    assert_eq!(info.enemies_spawned, 1);
+    assert!(reward < 0.0);
+    assert!(
+        (env.cannon().direction - config.gun_rotate_velocity * config.fast_delta_time).abs() < 1e-6
+    );
+}
+
+#[test]
+fn shooting_respects_the_cooldown() {
+    let mut env = new_env(ExperimentConfig::default());
+    let mut observation = env.reset(7);
+    let shoot = Action {
+        rotation: Rotation::Hold,
+        shoot: true,
+    };
+    while !observation.can_shoot {
+        observation = env.step(shoot).0;
+    }
+    assert_eq!(env.info().bullets_fired, 0);
+    let (observation, reward, _, info) = env.step(shoot);
+    assert_eq!(info.bullets_fired, 1);
+    assert_eq!(env.bullets().len(), 1);
+    assert!((reward + 0.1).abs() < 1e-6);
+    assert!(!observation.can_shoot);
+}
+
+#[test]
+fn episode_ends_after_training_time_and_is_reproducible() {
+    let config = ExperimentConfig {
+        training_time: 10.0,
+        ..ExperimentConfig::default()
+    };
+    let mut policy = Scripted(Action {
+        rotation: Rotation::Clockwise,
+        shoot: true,
+    });
+    let mut env = new_env(config.clone());
+    let first = env.run_episode(3, &mut policy);
+    let second = env.run_episode(3, &mut policy);
+    assert!(env.is_done());
+    assert!(first.elapsed_time > config.training_time);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn view_rays_surround_the_cannon_starting_behind_it() {
+    let total_rays = 20;
+    let direction = 1.0;
+    assert!(
+        (environment::view_ray_angle(direction, total_rays / 2, total_rays) - direction).abs()
+            < 1e-6
+    );
+    assert!(
+        (environment::view_ray_angle(direction, 0, total_rays) - (direction + PI)).abs() < 1e-6
+    );
+    for ray in 0..total_rays {
+        let angle = environment::view_ray_angle(5.9, ray, total_rays);
+        assert!((0.0..2.0 * PI).contains(&angle));
+    }
+}
+
+#[test]
+fn ray_readings_point_at_an_enemy_along_the_ray() {
+    let config = ExperimentConfig::default();
+    let mut env = new_env(config.clone());
+    let mut observation = env.reset(5);
+    let mut readings = 0;
+    while !env.is_done() {
+        let center = &env.cannon().position;
+        let total_rays = observation.rays.len();
+        for (ray, reading) in observation.rays.iter().enumerate() {
+            if *reading == 0.0 {
+                continue;
+            }
+            readings += 1;
+            let angle = environment::view_ray_angle(env.cannon().direction, ray, total_rays);
+            let distance = reading * config.view_ray_length + config.entity_sizes.cannon_radius;
+            assert!(env.enemies().iter().any(|enemy| {
+                let relative_position = enemy.position.difference(center);
+                let off_ray = relative_position.x * angle.sin() - relative_position.y * angle.cos();
+                (relative_position.magnitude() - distance).abs() < 1e-3
+                    && off_ray.abs() <= enemy.width / 2.0 + 1e-3
+            }));
+        }
+        observation = env
+            .step(Action {
+                rotation: Rotation::Clockwise,
+                shoot: false,
+            })
+            .0;
+    }
+    assert!(readings > 0);
+}
+
+#[test]
+fn step_callback_sees_every_action_and_can_stop_the_episode() {
+    let config = ExperimentConfig {
+        training_time: 2.0,
+        ..ExperimentConfig::default()
+    };
+    let scripted = Action {
+        rotation: Rotation::CounterClockwise,
+        shoot: true,
+    };
+    let mut policy = Scripted(scripted);
+    let mut env = new_env(config);
+    let mut steps = 0;
+    let info = env
+        .run_episode_with(5, &mut policy, |_, action| {
+            assert_eq!(action, scripted);
+            steps += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(info, env.run_episode(5, &mut policy));
+    assert!((steps as f32 * env.config().fast_delta_time - info.elapsed_time).abs() < 1e-3);
+
+    let stopped = env.run_episode_with(5, &mut policy, |environment, _| {
+        if environment.info().elapsed_time >= 1.0 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(stopped, None);
+    assert!(!env.is_done());
+}
@@ -0,0 +1,112 @@
+use std::{env, num::NonZero, sync::atomic::Ordering};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    fitness::{median, standard_deviation, FitnessAggregate},
+    lock_with_error,
+    multi_threading::{PopulationFiles, SharedResources},
+    trainer,
+};
+
+#[test]
+fn aggregates_synthetic_scores() {
+    let scores = [4.0, -2.0, 10.0, 0.0];
+    assert_eq!(FitnessAggregate::Mean.aggregate(&scores), 3.0);
+    assert_eq!(FitnessAggregate::Median.aggregate(&scores), 2.0);
+    assert_eq!(median(&[5.0, 1.0, 3.0]), 3.0);
+
+    // Squared deviations from the mean of 3 sum to 84 over 3 degrees of freedom.
+    let std = standard_deviation(&scores);
+    assert!((std - 28.0_f32.sqrt()).abs() < 1e-5);
+    let bound = FitnessAggregate::LowerConfidenceBound { z: 1.0 }.aggregate(&scores);
+    assert!((bound - (3.0 - std / 2.0)).abs() < 1e-5);
+}
+
+#[test]
+fn lower_confidence_bound_prefers_consistent_scores() {
+    let aggregate = FitnessAggregate::LowerConfidenceBound { z: 1.96 };
+    let lucky = [0.0, 0.0, 0.0, 12.0];
+    let steady = [2.5, 2.5, 2.5, 2.5];
+    assert!(FitnessAggregate::Mean.aggregate(&lucky) > FitnessAggregate::Mean.aggregate(&steady));
+    assert!(aggregate.aggregate(&lucky) < aggregate.aggregate(&steady));
+    assert_eq!(aggregate.aggregate(&[1.5]), 1.5);
+}
+
+fn new_shared_resources(config: ExperimentConfig, name: &str) -> SharedResources {
+    let shared_resources = SharedResources::new(
+        config,
+        8,
+        NonZero::new(4),
+        NonZero::new(2),
+        PopulationFiles::in_directory(&env::temp_dir(), name),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    shared_resources
+}
+
+#[test]
+fn every_genome_plays_every_episode() {
+    let config = ExperimentConfig {
+        training_time: 5.0,
+        episodes_per_genome: 3,
+        fitness_aggregate: FitnessAggregate::Median,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = new_shared_resources(config, "cannon_ai_fitness_episodes");
+    trainer::run_generation(&shared_resources);
+
+    let episode_scores = lock_with_error!(shared_resources.episode_scores);
+    let ai_scores = lock_with_error!(shared_resources.ai_scores);
+    for (scores, fitness) in episode_scores.iter().zip(ai_scores.iter()) {
+        assert_eq!(scores.len(), 3);
+        assert_eq!(median(scores), *fitness);
+    }
+}
+
+#[test]
+fn evaluation_plays_exactly_the_requested_episodes() {
+    let config = ExperimentConfig {
+        training_time: 2.0,
+        episodes_per_genome: 2,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = new_shared_resources(config, "cannon_ai_fitness_evaluate");
+    let episode_scores = trainer::evaluate(&shared_resources, 3);
+
+    assert!(episode_scores.iter().all(|scores| scores.len() == 3));
+    assert_eq!(shared_resources.generation.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn shared_seeds_give_identical_genomes_identical_scores() {
+    let config = ExperimentConfig {
+        training_time: 10.0,
+        episodes_per_genome: 2,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = new_shared_resources(config, "cannon_ai_fitness_shared");
+    {
+        let direction_ais = &mut lock_with_error!(shared_resources.direction_ais);
+        let shooting_ais = &mut lock_with_error!(shared_resources.shooting_ais);
+        for ai_index in 1..direction_ais.len() {
+            direction_ais[ai_index] = direction_ais[0].clone();
+            shooting_ais[ai_index] = shooting_ais[0].clone();
+        }
+    }
+    trainer::run_generation(&shared_resources);
+
+    let episode_scores = lock_with_error!(shared_resources.episode_scores);
+    assert!(episode_scores
+        .iter()
+        .all(|scores| *scores == episode_scores[0]));
+}
+
+#[test]
+fn config_rejects_zero_episodes() {
+    let config = ExperimentConfig {
+        episodes_per_genome: 0,
+        ..ExperimentConfig::default()
+    };
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,104 @@
+use cannon_ai::{
+    config::ExperimentConfig, initializer::Initializer, neural_network::NeuralNetwork,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const LAYER_SIZES: [usize; 3] = [40, 30, 20];
+
+fn parameters(initializer: Initializer, seed: u64) -> (Vec<f32>, Vec<f32>) {
+    let mut network =
+        NeuralNetwork::new_random(&LAYER_SIZES, initializer, &mut StdRng::seed_from_u64(seed))
+            .unwrap();
+    let parameters = network.parameters_mut();
+    let (weights, biases) = parameters.split_at(LAYER_SIZES.len() - 1);
+    (
+        weights.iter().flat_map(|tensor| tensor.to_vec()).collect(),
+        biases.iter().flat_map(|tensor| tensor.to_vec()).collect(),
+    )
+}
+
+fn mean_and_std(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / values.len() as f32;
+    (mean, variance.sqrt())
+}
+
+#[test]
+fn same_seed_gives_identical_networks() {
+    for initializer in [
+        Initializer::UniformSymmetric { limit: 0.3 },
+        Initializer::Xavier,
+        Initializer::He,
+        Initializer::Normal { std: 0.2 },
+    ] {
+        assert_eq!(parameters(initializer, 4), parameters(initializer, 4));
+        assert_ne!(parameters(initializer, 4), parameters(initializer, 5));
+    }
+}
+
+#[test]
+fn xavier_is_symmetric_within_its_limit() {
+    let (weights, biases) = parameters(Initializer::Xavier, 1);
+    let widest_limit = (6.0_f32 / (30.0 + 20.0)).sqrt();
+    assert!(weights.iter().all(|weight| weight.abs() <= widest_limit));
+    assert!(weights.iter().any(|weight| *weight < 0.0));
+    assert!(mean_and_std(&weights).0.abs() < 0.02);
+    assert!(biases.iter().all(|bias| *bias == 0.0));
+}
+
+#[test]
+fn normal_matches_requested_std() {
+    let (weights, biases) = parameters(Initializer::Normal { std: 0.2 }, 2);
+    let (mean, std) = mean_and_std(&weights);
+    assert!(mean.abs() < 0.02, "mean {mean}");
+    assert!((std - 0.2).abs() < 0.02, "std {std}");
+    assert!(biases.iter().any(|bias| *bias != 0.0));
+}
+
+#[test]
+fn he_scales_with_fan_in() {
+    let mut network = NeuralNetwork::new_random(
+        &[200, 100, 2],
+        Initializer::He,
+        &mut StdRng::seed_from_u64(3),
+    )
+    .unwrap();
+    let std = mean_and_std(network.parameters_mut()[0]).1;
+    assert!((std - 0.1).abs() < 0.01, "std {std}");
+}
+
+#[test]
+fn zeros_gives_an_all_zero_network() {
+    let (weights, biases) = parameters(Initializer::Zeros, 0);
+    assert!(weights
+        .iter()
+        .chain(biases.iter())
+        .all(|value| *value == 0.0));
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0);
+    assert!(
+        NeuralNetwork::new_random(&LAYER_SIZES, Initializer::Normal { std: -1.0 }, &mut rng)
+            .is_err()
+    );
+    assert!(NeuralNetwork::new_random(
+        &LAYER_SIZES,
+        Initializer::UniformSymmetric { limit: 0.0 },
+        &mut rng
+    )
+    .is_err());
+}
+
+#[test]
+fn config_selects_the_initializer() {
+    let config: ExperimentConfig =
+        toml::from_str("initializer = { kind = \"normal\", std = 0.1 }").unwrap();
+    assert_eq!(config.initializer, Initializer::Normal { std: 0.1 });
+    assert_eq!(ExperimentConfig::default().initializer, Initializer::Xavier);
+}
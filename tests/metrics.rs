@@ -0,0 +1,152 @@
+use std::{env, fs, num::NonZero, sync::atomic::Ordering, time::Duration};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    environment::StepInfo,
+    metrics::{GenerationMetrics, MetricsFormat, MetricsLog, CSV_HEADER},
+    multi_threading::{PopulationFiles, SharedResources},
+    trainer::{self, SimulationOptions},
+};
+
+#[test]
+fn summarizes_a_generation() {
+    let totals = [
+        StepInfo {
+            enemies_killed: 3,
+            bullets_fired: 5,
+            enemies_reached_cannon: 1,
+            ..StepInfo::default()
+        },
+        StepInfo {
+            enemies_killed: 1,
+            bullets_fired: 4,
+            enemies_reached_cannon: 2,
+            ..StepInfo::default()
+        },
+    ];
+    let metrics = GenerationMetrics::new(
+        7,
+        &[4.0, -2.0, 10.0, 0.0],
+        &totals,
+        Duration::from_millis(1500),
+        0xabc,
+    );
+    assert_eq!(metrics.generation, 7);
+    assert_eq!(metrics.min_score, -2.0);
+    assert_eq!(metrics.mean_score, 3.0);
+    assert_eq!(metrics.median_score, 2.0);
+    assert_eq!(metrics.max_score, 10.0);
+    assert!((metrics.std_dev_score - 28.0_f32.sqrt()).abs() < 1e-5);
+    assert_eq!(
+        (
+            metrics.enemies_killed,
+            metrics.bullets_fired,
+            metrics.enemies_escaped
+        ),
+        (4, 9, 3)
+    );
+    assert_eq!(metrics.wall_time_secs, 1.5);
+    assert_eq!(metrics.config_hash, "0000000000000abc");
+}
+
+#[test]
+fn config_hash_follows_the_config() {
+    let config = ExperimentConfig::default();
+    assert_eq!(config.hash(), ExperimentConfig::default().hash());
+    let changed = ExperimentConfig {
+        bullet_speed: 151.0,
+        ..ExperimentConfig::default()
+    };
+    assert_ne!(config.hash(), changed.hash());
+}
+
+#[test]
+fn picks_the_format_from_the_extension() {
+    assert_eq!(
+        MetricsFormat::from_path("run.csv".as_ref()).unwrap(),
+        MetricsFormat::Csv
+    );
+    assert_eq!(
+        MetricsFormat::from_path("run.jsonl".as_ref()).unwrap(),
+        MetricsFormat::JsonLines
+    );
+    assert!(MetricsFormat::from_path("run.txt".as_ref()).is_err());
+}
+
+fn train_with_log(path: &std::path::Path, generations: usize) {
+    let shared_resources = SharedResources::new(
+        ExperimentConfig {
+            training_time: 2.0,
+            ..ExperimentConfig::default()
+        },
+        5,
+        NonZero::new(4),
+        NonZero::new(2),
+        PopulationFiles::in_directory(&env::temp_dir(), "metrics_missing"),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    trainer::run_simulation(
+        shared_resources,
+        SimulationOptions {
+            startup_delay: Duration::ZERO,
+            max_generations: Some(generations),
+            evolve: true,
+            metrics_log: Some(MetricsLog::open(path).unwrap()),
+            checkpoint_every: None,
+            replay_dir: None,
+        },
+    )
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn appends_one_csv_row_per_generation_under_a_single_header() {
+    let path = env::temp_dir().join("cannon_ai_metrics_test.csv");
+    let _ = fs::remove_file(&path);
+    train_with_log(&path, 2);
+    train_with_log(&path, 1);
+
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines = contents.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], CSV_HEADER);
+    let columns = CSV_HEADER.split(',').count();
+    assert!(lines[1..]
+        .iter()
+        .all(|line| line.split(',').count() == columns));
+    assert!(lines[2].starts_with("1,"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn json_lines_records_parse_back() {
+    let path = env::temp_dir().join("cannon_ai_metrics_test.jsonl");
+    let _ = fs::remove_file(&path);
+    train_with_log(&path, 2);
+
+    let contents = fs::read_to_string(&path).unwrap();
+    let records = contents
+        .lines()
+        .map(|line| serde_json::from_str::<GenerationMetrics>(line).unwrap())
+        .collect::<Vec<GenerationMetrics>>();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].generation, 0);
+    assert_eq!(records[1].generation, 1);
+    assert!(records[0].min_score <= records[0].median_score);
+    assert!(records[0].median_score <= records[0].max_score);
+    assert!(records[1].wall_time_secs >= records[0].wall_time_secs);
+    assert_eq!(
+        records[0].config_hash,
+        format!(
+            "{:016x}",
+            ExperimentConfig {
+                training_time: 2.0,
+                ..ExperimentConfig::default()
+            }
+            .hash()
+        )
+    );
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,265 @@
+use std::{env, fs};
+
+use cannon_ai::{
+    initializer::Initializer,
+    neural_network::{Activation, NeuralNetwork},
+    onnx::{self, ModelProto, NodeProto, OnnxNames, TensorProto, FLOAT_TYPE, OPSET_VERSION},
+};
+use na::{DMatrix, DVector};
+use prost::Message;
+use rand::{rngs::StdRng, SeedableRng};
+
+const NAMES: OnnxNames = OnnxNames {
+    graph: "test_graph",
+    input: "rays",
+    output: "scores",
+    doc_string: "Test network",
+};
+
+fn network(activations: &[Activation]) -> NeuralNetwork {
+    let mut rng = StdRng::seed_from_u64(17);
+    NeuralNetwork::new_random_unchecked(&[6, 5, 4, 3], Initializer::Normal { std: 0.5 }, &mut rng)
+        .with_activations(activations)
+        .unwrap()
+}
+fn read_back(network: &NeuralNetwork) -> ModelProto {
+    let path = env::temp_dir().join(format!("cannon_ai_onnx_{:?}.onnx", network.activations()));
+    onnx::export(network, &NAMES, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    ModelProto::decode(bytes.as_slice()).unwrap()
+}
+fn initializer<'a>(model: &'a ModelProto, name: &str) -> &'a TensorProto {
+    model
+        .graph
+        .as_ref()
+        .unwrap()
+        .initializer
+        .iter()
+        .find(|tensor| tensor.name == name)
+        .unwrap()
+}
+/// Evaluates the exported graph for one input row, following the ONNX operator definitions.
+fn evaluate(model: &ModelProto, input: &[f32]) -> Vec<f32> {
+    let graph = model.graph.as_ref().unwrap();
+    let mut values = vec![(graph.input[0].name.clone(), input.to_vec())];
+    let value = |values: &[(String, Vec<f32>)], name: &str| {
+        values
+            .iter()
+            .find(|(value_name, _)| value_name == name)
+            .unwrap()
+            .1
+            .clone()
+    };
+    for node in graph.node.iter() {
+        let x = value(&values, &node.input[0]);
+        let output = match node.op_type.as_str() {
+            "Gemm" => {
+                assert_eq!(attribute_i(node, "transB"), 1);
+                let weights = initializer(model, &node.input[1]);
+                let biases = initializer(model, &node.input[2]);
+                let (rows, columns) = (weights.dims[0] as usize, weights.dims[1] as usize);
+                (0..rows)
+                    .map(|row| {
+                        (0..columns)
+                            .map(|column| weights.float_data[row * columns + column] * x[column])
+                            .sum::<f32>()
+                            + biases.float_data[row]
+                    })
+                    .collect()
+            }
+            "Tanh" => x.iter().map(|x| x.tanh()).collect(),
+            "Sigmoid" => x.iter().map(|x| 1.0 / (1.0 + (-x).exp())).collect(),
+            "Relu" => x.iter().map(|x| x.max(0.0)).collect(),
+            "LeakyRelu" => {
+                let alpha = node
+                    .attribute
+                    .iter()
+                    .find(|attribute| attribute.name == "alpha")
+                    .unwrap()
+                    .f;
+                x.iter()
+                    .map(|x| if *x < 0.0 { alpha * x } else { *x })
+                    .collect()
+            }
+            "Softmax" => {
+                assert_eq!(attribute_i(node, "axis"), -1);
+                let max = x.iter().cloned().fold(f32::MIN, f32::max);
+                let total = x.iter().map(|x| (x - max).exp()).sum::<f32>();
+                x.iter().map(|x| (x - max).exp() / total).collect()
+            }
+            op_type => panic!("Unexpected operator {op_type}"),
+        };
+        values.push((node.output[0].clone(), output));
+    }
+    value(&values, &graph.output[0].name)
+}
+fn attribute_i(node: &NodeProto, name: &str) -> i64 {
+    node.attribute
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .unwrap()
+        .i
+}
+
+#[test]
+fn exports_the_graph_structure() {
+    let network = network(&[Activation::Tanh, Activation::LeakyRelu, Activation::Softmax]);
+    let model = read_back(&network);
+    assert_eq!(model.opset_import[0].version, OPSET_VERSION);
+    assert_eq!(model.opset_import[0].domain, "");
+    let graph = model.graph.as_ref().unwrap();
+    assert_eq!(graph.name, "test_graph");
+    assert_eq!(
+        graph
+            .node
+            .iter()
+            .map(|node| node.op_type.as_str())
+            .collect::<Vec<&str>>(),
+        ["Gemm", "Tanh", "Gemm", "LeakyRelu", "Gemm", "Softmax"]
+    );
+    assert_eq!(graph.node[0].input[0], "rays");
+    assert_eq!(graph.node[5].output[0], "scores");
+
+    for (info, name, size) in [
+        (&graph.input[0], "rays", 6),
+        (&graph.output[0], "scores", 3),
+    ] {
+        assert_eq!(info.name, name);
+        let tensor_type = info.r#type.as_ref().unwrap().tensor_type.as_ref().unwrap();
+        assert_eq!(tensor_type.elem_type, FLOAT_TYPE);
+        let dims = &tensor_type.shape.as_ref().unwrap().dim;
+        assert_eq!(dims[0].dim_param.as_deref(), Some("batch"));
+        assert_eq!(dims[1].dim_value, Some(size));
+    }
+}
+
+#[test]
+fn exports_the_weights_row_major() {
+    let network = network(&[Activation::Relu, Activation::Sigmoid, Activation::Linear]);
+    let model = read_back(&network);
+    assert_eq!(model.graph.as_ref().unwrap().initializer.len(), 6);
+    for (layer, (weight, bias)) in network.weights().iter().zip(network.biases()).enumerate() {
+        let weights = initializer(&model, &format!("layer{layer}.weights"));
+        assert_eq!(weights.data_type, FLOAT_TYPE);
+        assert_eq!(weights.dims, [weight.nrows() as i64, weight.ncols() as i64]);
+        let exported = DMatrix::from_row_slice(weight.nrows(), weight.ncols(), &weights.float_data);
+        assert_eq!(&exported, weight);
+        let biases = initializer(&model, &format!("layer{layer}.biases"));
+        assert_eq!(biases.float_data, bias.as_slice());
+    }
+    // A linear output layer needs no activation node.
+    let graph = model.graph.as_ref().unwrap();
+    assert_eq!(graph.node.last().unwrap().op_type, "Gemm");
+    assert_eq!(graph.node.last().unwrap().output[0], "scores");
+}
+
+#[test]
+fn exported_graph_computes_the_same_outputs() {
+    for activations in [
+        [Activation::Tanh, Activation::Tanh, Activation::Tanh],
+        [Activation::LeakyRelu, Activation::Relu, Activation::Softmax],
+        [Activation::Sigmoid, Activation::Linear, Activation::Linear],
+    ] {
+        let network = network(&activations);
+        let model = read_back(&network);
+        let input = [0.9, -0.3, 0.0, 1.0, 0.25, -1.0];
+        let expected = network.run(&DVector::from_column_slice(&input)).unwrap();
+        let output = evaluate(&model, &input);
+        for (output, expected) in output.iter().zip(expected.iter()) {
+            assert!((output - expected).abs() < 1e-5, "{output} != {expected}");
+        }
+    }
+}
+
+/// Reads the top-level field numbers of a protobuf message without any schema.
+fn field_numbers(mut bytes: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    fn varint(bytes: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        for shift in (0..).step_by(7) {
+            let byte = bytes[0];
+            *bytes = &bytes[1..];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        value
+    }
+    let mut fields = vec![];
+    while !bytes.is_empty() {
+        let key = varint(&mut bytes);
+        let payload = match key & 7 {
+            0 => {
+                varint(&mut bytes);
+                vec![]
+            }
+            2 => {
+                let length = varint(&mut bytes) as usize;
+                let payload = bytes[..length].to_vec();
+                bytes = &bytes[length..];
+                payload
+            }
+            5 => {
+                bytes = &bytes[4..];
+                vec![]
+            }
+            wire_type => panic!("Unexpected wire type {wire_type}"),
+        };
+        fields.push((key >> 3, payload));
+    }
+    fields
+}
+
+#[test]
+fn uses_the_official_field_numbers() {
+    let network = network(&[Activation::Tanh, Activation::Tanh, Activation::Tanh]);
+    let bytes = onnx::model(&network, &NAMES).encode_to_vec();
+
+    // ModelProto: ir_version 1, producer_name 2, graph 7, opset_import 8.
+    let model_fields = field_numbers(&bytes);
+    let numbers = model_fields
+        .iter()
+        .map(|(number, _)| *number)
+        .collect::<Vec<u64>>();
+    for number in [1, 2, 7, 8] {
+        assert!(
+            numbers.contains(&number),
+            "ModelProto field {number} missing"
+        );
+    }
+    // GraphProto: node 1, name 2, initializer 5, input 11, output 12.
+    let graph = &model_fields
+        .iter()
+        .find(|(number, _)| *number == 7)
+        .unwrap()
+        .1;
+    let graph_fields = field_numbers(graph);
+    for number in [1, 2, 5, 11, 12] {
+        assert!(graph_fields.iter().any(|(field, _)| *field == number));
+    }
+    // NodeProto: op_type 4.
+    let node = &graph_fields
+        .iter()
+        .find(|(number, _)| *number == 1)
+        .unwrap()
+        .1;
+    let op_type = &field_numbers(node)
+        .into_iter()
+        .find(|(number, _)| *number == 4)
+        .unwrap()
+        .1;
+    assert_eq!(op_type, b"Gemm");
+    // TensorProto: name 8.
+    let tensor = &graph_fields
+        .iter()
+        .find(|(number, _)| *number == 5)
+        .unwrap()
+        .1;
+    let name = &field_numbers(tensor)
+        .into_iter()
+        .find(|(number, _)| *number == 8)
+        .unwrap()
+        .1;
+    assert_eq!(name, b"layer0.weights");
+}
@@ -0,0 +1,213 @@
+use std::{env, fs, num::NonZero, sync::atomic::Ordering, sync::Arc, time::Duration};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    entity::Point,
+    environment::{CannonEnv, NetworkPolicy},
+    initializer::Initializer,
+    lock_with_error,
+    multi_threading::{PopulationFiles, SharedResources},
+    neural_network::NeuralNetwork,
+    replay::{Replay, ReplayPlayer, REPLAY_MAGIC},
+    trainer::{self, SimulationOptions},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn record(seed: u64) -> Replay {
+    let config = ExperimentConfig::default();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let direction_ai = NeuralNetwork::new_random_unchecked(
+        &[config.total_view_rays, 10, 3],
+        Initializer::Xavier,
+        &mut rng,
+    );
+    let shooting_ai = NeuralNetwork::new_random_unchecked(
+        &[config.total_view_rays, 10, 2],
+        Initializer::Xavier,
+        &mut rng,
+    );
+    Replay::record(
+        Arc::new(config),
+        Point {
+            x: 1000.0,
+            y: 750.0,
+        },
+        seed,
+        "test".to_string(),
+        &mut NetworkPolicy {
+            direction_ai: &direction_ai,
+            shooting_ai: &shooting_ai,
+        },
+    )
+}
+
+fn fingerprint(environment: &CannonEnv) -> (String, usize, usize) {
+    (
+        format!(
+            "{:?} {:?} {}",
+            environment.info(),
+            environment.observation(),
+            environment.cannon().direction
+        ),
+        environment.bullets().len(),
+        environment.enemies().len(),
+    )
+}
+
+#[test]
+fn replays_are_compact_and_round_trip() {
+    let replay = record(3);
+    let bytes = replay.encode().unwrap();
+    assert_eq!(&bytes[..4], REPLAY_MAGIC);
+    assert!(
+        bytes.len() < 1500 + replay.actions.len() / 8,
+        "{} bytes",
+        bytes.len()
+    );
+    assert_eq!(Replay::decode(&bytes).unwrap(), replay);
+}
+
+#[test]
+fn playing_back_reproduces_the_episode() {
+    let replay = record(8);
+    let mut player = ReplayPlayer::new(&replay);
+    assert_eq!(player.total_steps(), replay.actions.len());
+    while player.advance() {}
+    assert!(player.is_finished());
+    let info = player.environment().info();
+    assert_eq!(info.score, replay.header.score);
+    assert_eq!(info.enemies_killed, replay.header.enemies_killed);
+}
+
+#[test]
+fn seeking_matches_playing_forward() {
+    let replay = record(11);
+    let mut sequential = ReplayPlayer::new(&replay);
+    let mut seeking = ReplayPlayer::new(&replay);
+    let total_steps = replay.actions.len();
+    let targets = [
+        total_steps / 2 + 21,
+        total_steps / 2 + 22,
+        120,
+        0,
+        499,
+        500,
+        total_steps - 1,
+    ];
+    let mut expected = vec![];
+    for target in targets {
+        sequential.seek(0);
+        for _ in 0..target {
+            sequential.advance();
+        }
+        expected.push(fingerprint(sequential.environment()));
+    }
+    for (target, expected) in targets.into_iter().zip(expected) {
+        seeking.seek(target);
+        assert_eq!(seeking.position(), target);
+        assert_eq!(fingerprint(seeking.environment()), expected);
+    }
+    seeking.seek(usize::MAX);
+    assert!(seeking.is_finished());
+}
+
+#[test]
+fn rejects_corrupt_replays() {
+    let replay = record(2);
+    let bytes = replay.encode().unwrap();
+
+    assert!(Replay::decode(b"CNAI\x01\x00")
+        .unwrap_err()
+        .contains("Not a replay"));
+    let mut future_version = bytes.clone();
+    future_version[4] = 9;
+    assert!(Replay::decode(&future_version)
+        .unwrap_err()
+        .contains("version 9"));
+    assert!(Replay::decode(&bytes[..bytes.len() / 2]).is_err());
+
+    let header_length = u32::from_le_bytes(bytes[6..10].try_into().unwrap()) as usize;
+    let count_offset = 10 + header_length;
+    let mut wrong_count = bytes.clone();
+    wrong_count[count_offset..count_offset + 4].copy_from_slice(&5u32.to_le_bytes());
+    assert!(Replay::decode(&wrong_count)
+        .unwrap_err()
+        .contains("header says 5"));
+}
+
+#[test]
+fn training_saves_the_best_episode_of_each_generation() {
+    let directory = env::temp_dir().join("cannon_ai_replays");
+    let _ = fs::remove_dir_all(&directory);
+    let shared_resources = SharedResources::new(
+        ExperimentConfig {
+            training_time: 2.0,
+            episodes_per_genome: 2,
+            ..ExperimentConfig::default()
+        },
+        6,
+        NonZero::new(3),
+        NonZero::new(1),
+        PopulationFiles::in_directory(&directory, "missing"),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    trainer::run_simulation(
+        shared_resources.clone(),
+        SimulationOptions {
+            startup_delay: Duration::ZERO,
+            max_generations: Some(1),
+            evolve: false,
+            metrics_log: None,
+            checkpoint_every: None,
+            replay_dir: Some(directory.clone()),
+        },
+    )
+    .join()
+    .unwrap();
+
+    let replay = Replay::load(&directory.join("generation_0.replay")).unwrap();
+    let best_score = lock_with_error!(shared_resources.episode_scores)
+        .iter()
+        .flatten()
+        .cloned()
+        .fold(f32::MIN, f32::max);
+    assert_eq!(replay.header.score, best_score);
+    assert!(replay.header.label.starts_with("Generation 0, AI"));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn saved_replays_keep_the_arena_the_episode_was_played_in() {
+    let directory = env::temp_dir().join("cannon_ai_replays_resized");
+    let _ = fs::remove_dir_all(&directory);
+    let shared_resources = SharedResources::new(
+        ExperimentConfig {
+            training_time: 2.0,
+            ..ExperimentConfig::default()
+        },
+        9,
+        NonZero::new(4),
+        NonZero::new(2),
+        PopulationFiles::in_directory(&directory, "missing"),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    shared_resources.set_dimensions(640.0, 480.0);
+    trainer::run_generation(&shared_resources);
+    shared_resources.set_dimensions(1600.0, 900.0);
+
+    let path = trainer::save_best_replay(&shared_resources, 0, &directory).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!((replay.header.width, replay.header.height), (640.0, 480.0));
+    let best_score = lock_with_error!(shared_resources.ai_scores)
+        .iter()
+        .cloned()
+        .fold(f32::MIN, f32::max);
+    assert_eq!(replay.header.score, best_score);
+
+    let mut player = ReplayPlayer::new(&replay);
+    while player.advance() {}
+    assert_eq!(player.environment().info().score, replay.header.score);
+    fs::remove_dir_all(&directory).unwrap();
+}
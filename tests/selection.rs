@@ -0,0 +1,208 @@
+use cannon_ai::{
+    config::ExperimentConfig,
+    selection::{
+        ranked_indices, Elitism, Rank, RankedScores, Roulette, Selection, SelectionStrategy,
+        Tournament, Truncation,
+    },
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const SCORES: [f32; 6] = [3.0, -2.0, 10.0, 0.5, 7.0, -4.0];
+const DRAWS: usize = 20_000;
+
+fn parent_counts(strategy: &dyn SelectionStrategy, scores: &[f32]) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut counts = vec![0; scores.len()];
+    let scores = RankedScores::new(scores);
+    for _ in 0..DRAWS {
+        counts[strategy.select_parent(&scores, &mut rng)] += 1;
+    }
+    counts
+}
+
+fn sorted(values: &[usize]) -> Vec<usize> {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    values
+}
+
+#[test]
+fn ranks_from_best_to_worst() {
+    assert_eq!(ranked_indices(&SCORES), vec![2, 4, 0, 3, 1, 5]);
+}
+
+#[test]
+fn truncation_keeps_and_breeds_from_the_top_fraction() {
+    let strategy = Truncation { fraction: 0.5 };
+    assert_eq!(
+        sorted(&strategy.survivors(&RankedScores::new(&SCORES))),
+        vec![0, 2, 4]
+    );
+    let counts = parent_counts(&strategy, &SCORES);
+    assert_eq!(counts[1] + counts[3] + counts[5], 0);
+    assert!(counts[0] > 0 && counts[2] > 0 && counts[4] > 0);
+}
+
+#[test]
+fn truncation_keeps_at_least_one_ai() {
+    let strategy = Truncation { fraction: 0.01 };
+    assert_eq!(&*strategy.survivors(&RankedScores::new(&SCORES)), &[2]);
+}
+
+#[test]
+fn tournament_of_one_is_uniform() {
+    let counts = parent_counts(&Tournament { size: 1 }, &SCORES);
+    let expected = DRAWS / SCORES.len();
+    assert!(counts
+        .iter()
+        .all(|count| count.abs_diff(expected) < expected / 10));
+}
+
+#[test]
+fn larger_tournaments_favour_the_best() {
+    let small = parent_counts(&Tournament { size: 2 }, &SCORES);
+    let large = parent_counts(&Tournament { size: 5 }, &SCORES);
+    assert!(large[2] > small[2]);
+    assert!(large[5] <= small[5]);
+    assert!(Tournament { size: 3 }
+        .survivors(&RankedScores::new(&SCORES))
+        .is_empty());
+}
+
+#[test]
+fn roulette_is_proportional_to_shifted_fitness() {
+    let scores = [1.0, 2.0, 3.0, 4.0];
+    let counts = parent_counts(&Roulette, &scores);
+    // Shifted weights are roughly 0, 1, 2 and 3.
+    assert!(counts[0] < DRAWS / 100);
+    let ratio = counts[3] as f32 / counts[1] as f32;
+    assert!((ratio - 3.0).abs() < 0.3, "ratio {ratio}");
+}
+
+#[test]
+fn roulette_handles_equal_scores() {
+    let counts = parent_counts(&Roulette, &[-1.0; 4]);
+    assert!(counts.iter().all(|count| *count > 0));
+}
+
+#[test]
+fn rank_weights_by_position() {
+    let counts = parent_counts(&Rank, &SCORES);
+    // The best of six has weight 6 and the worst weight 1.
+    let ratio = counts[2] as f32 / counts[5] as f32;
+    assert!((ratio - 6.0).abs() < 0.8, "ratio {ratio}");
+    assert!(counts[2] > counts[4] && counts[4] > counts[0] && counts[0] > counts[3]);
+}
+
+#[test]
+fn elitism_keeps_the_top_k_and_delegates_parents() {
+    let strategy = Elitism {
+        count: 2,
+        parents: Box::new(Tournament { size: 2 }),
+    };
+    assert_eq!(
+        sorted(&strategy.survivors(&RankedScores::new(&SCORES))),
+        vec![2, 4]
+    );
+    assert_eq!(
+        parent_counts(&strategy, &SCORES),
+        parent_counts(&Tournament { size: 2 }, &SCORES)
+    );
+
+    let with_truncation = Elitism {
+        count: 1,
+        parents: Box::new(Truncation { fraction: 0.5 }),
+    };
+    assert_eq!(
+        sorted(&with_truncation.survivors(&RankedScores::new(&SCORES))),
+        vec![0, 2, 4]
+    );
+}
+
+#[test]
+fn config_selects_and_validates_the_strategy() {
+    let config: ExperimentConfig = toml::from_str(
+        "selection = { kind = \"elitism\", count = 2, parents = { kind = \"tournament\", size = 3 } }",
+    )
+    .unwrap();
+    assert_eq!(
+        config.selection,
+        Selection::Elitism {
+            count: 2,
+            parents: Box::new(Selection::Tournament { size: 3 })
+        }
+    );
+    assert_eq!(
+        sorted(
+            &config
+                .selection
+                .strategy()
+                .survivors(&RankedScores::new(&SCORES))
+        ),
+        vec![2, 4]
+    );
+
+    let invalid = ExperimentConfig {
+        selection: Selection::Tournament { size: 0 },
+        ..ExperimentConfig::default()
+    };
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn truncation_must_replace_some_ais() {
+    for fraction in [0.0, 1.0, 1.5] {
+        let config = ExperimentConfig {
+            selection: Selection::Truncation { fraction },
+            ..ExperimentConfig::default()
+        };
+        assert!(config.validate().is_err(), "fraction {fraction}");
+    }
+}
+
+#[test]
+fn nan_scores_rank_last() {
+    let scores = [3.0, f32::NAN, 10.0, f32::NEG_INFINITY, f32::NAN, 7.0];
+    let ranking = ranked_indices(&scores);
+    assert_eq!(&ranking[..3], &[2, 5, 0]);
+    assert_eq!(sorted(&ranking[3..]), vec![1, 3, 4]);
+
+    let many = (0..100)
+        .map(|index| {
+            if index % 3 == 0 {
+                f32::NAN
+            } else {
+                index as f32
+            }
+        })
+        .collect::<Vec<f32>>();
+    let ranking = ranked_indices(&many);
+    assert_eq!(ranking[0], 98);
+    assert!(ranking[..66].iter().all(|index| !many[*index].is_nan()));
+    assert!(parent_counts(&Tournament { size: 3 }, &scores)[2] > 0);
+}
+
+#[test]
+fn elitism_must_replace_some_ais() {
+    let elitism = |count| ExperimentConfig {
+        population_size: 4,
+        selection: Selection::Elitism {
+            count,
+            parents: Box::new(Selection::Rank),
+        },
+        ..ExperimentConfig::default()
+    };
+    assert!(elitism(3).validate().is_ok());
+    for count in [4, 10] {
+        let error = elitism(count).validate().unwrap_err();
+        assert!(error.contains("Elitism count"), "{error}");
+    }
+    let nested = ExperimentConfig {
+        selection: Selection::Elitism {
+            count: 1,
+            parents: Box::new(elitism(4).selection),
+        },
+        ..elitism(1)
+    };
+    assert!(nested.validate().is_err());
+}
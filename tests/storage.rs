@@ -0,0 +1,172 @@
+use std::{
+    env, fs, io,
+    num::NonZero,
+    path::{Path, PathBuf},
+};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    lock_with_error,
+    multi_threading::{PopulationFiles, SharedResources},
+    neural_network::NeuralNetwork,
+    storage::{self, LoadError, MismatchPolicy},
+};
+
+fn files(name: &str, on_mismatch: MismatchPolicy) -> PopulationFiles {
+    PopulationFiles {
+        on_mismatch,
+        ..PopulationFiles::in_directory(&env::temp_dir(), name)
+    }
+}
+fn paths(name: &str) -> [PathBuf; 3] {
+    let files = files(name, MismatchPolicy::Error);
+    [
+        files.direction_path.unwrap(),
+        files.shooting_path.unwrap(),
+        files.checkpoint_path.unwrap(),
+    ]
+}
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    PathBuf::from(backup_path)
+}
+fn remove_files(name: &str) {
+    for path in paths(name) {
+        let _ = fs::remove_file(backup_path(&path));
+        let _ = fs::remove_file(path);
+    }
+}
+fn load(
+    name: &str,
+    config: ExperimentConfig,
+    total_ais: usize,
+    on_mismatch: MismatchPolicy,
+) -> Result<SharedResources, io::Error> {
+    SharedResources::new(
+        config,
+        4,
+        NonZero::new(total_ais),
+        NonZero::new(1),
+        files(name, on_mismatch),
+    )
+}
+/// Saves a random population of `total_ais` under `name`.
+fn save(name: &str, total_ais: usize) -> SharedResources {
+    remove_files(name);
+    let shared_resources = load(
+        name,
+        ExperimentConfig::default(),
+        total_ais,
+        MismatchPolicy::Error,
+    )
+    .unwrap();
+    shared_resources.save_ais().unwrap();
+    shared_resources
+}
+fn network_json(network: &NeuralNetwork) -> String {
+    serde_json::to_string(network).unwrap()
+}
+
+#[test]
+fn corrupt_files_are_errors_instead_of_panics() {
+    let name = "storage_corrupt";
+    save(name, 3);
+    fs::write(&paths(name)[0], "[{\"input_size\": 20,").unwrap();
+
+    let error = load(name, ExperimentConfig::default(), 3, MismatchPolicy::Error)
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("storage_corrupt_direction.json"));
+    assert!(matches!(
+        storage::load_networks(&paths(name)[0]),
+        Err(LoadError::Parse { .. })
+    ));
+
+    let shared_resources =
+        load(name, ExperimentConfig::default(), 3, MismatchPolicy::Random).unwrap();
+    assert_eq!(lock_with_error!(shared_resources.direction_ais).len(), 3);
+    assert!(!paths(name)[0].exists());
+    assert_eq!(
+        fs::read_to_string(backup_path(&paths(name)[0])).unwrap(),
+        "[{\"input_size\": 20,"
+    );
+    remove_files(name);
+}
+
+#[test]
+fn rejects_networks_whose_layers_do_not_chain() {
+    let name = "storage_layers";
+    save(name, 1);
+    let mut json =
+        serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&paths(name)[1]).unwrap())
+            .unwrap();
+    json[0]["input_size"] = 7.into();
+    fs::write(&paths(name)[1], json.to_string()).unwrap();
+
+    assert!(matches!(
+        storage::load_networks(&paths(name)[1]),
+        Err(LoadError::Parse { .. })
+    ));
+    remove_files(name);
+}
+
+#[test]
+fn checks_input_and_output_sizes() {
+    let name = "storage_shapes";
+    save(name, 2);
+    let fewer_rays = ExperimentConfig {
+        total_view_rays: 10,
+        ..ExperimentConfig::default()
+    };
+    let error = load(name, fewer_rays.clone(), 2, MismatchPolicy::Adapt)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("10 view rays"));
+
+    let shared_resources = load(name, fewer_rays, 2, MismatchPolicy::Random).unwrap();
+    assert_eq!(
+        lock_with_error!(shared_resources.direction_ais)[0].input_size(),
+        10
+    );
+
+    let [direction_path, shooting_path, _] = paths(name);
+    assert!(!shooting_path.exists());
+    fs::copy(backup_path(&shooting_path), &direction_path).unwrap();
+    let networks = storage::load_networks(&direction_path).unwrap().unwrap();
+    assert!(matches!(
+        storage::check_networks(&direction_path, &networks, 20, 3),
+        Err(LoadError::OutputSize {
+            expected: 3,
+            found: 2,
+            ..
+        })
+    ));
+    remove_files(name);
+}
+
+#[test]
+fn adapts_the_population_size_only_when_asked() {
+    let name = "storage_adapt";
+    let saved = save(name, 3);
+    let saved_ais = lock_with_error!(saved.direction_ais).clone();
+
+    let error = load(name, ExperimentConfig::default(), 5, MismatchPolicy::Error)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("holds 3 AIs but 5 are needed"));
+
+    let grown = load(name, ExperimentConfig::default(), 5, MismatchPolicy::Adapt).unwrap();
+    let grown_ais = lock_with_error!(grown.direction_ais);
+    assert_eq!(grown_ais.len(), 5);
+    assert_eq!(lock_with_error!(grown.shooting_ais).len(), 5);
+    assert_eq!(network_json(&grown_ais[3]), network_json(&saved_ais[0]));
+    assert_eq!(network_json(&grown_ais[4]), network_json(&saved_ais[1]));
+
+    let shrunk = load(name, ExperimentConfig::default(), 2, MismatchPolicy::Adapt).unwrap();
+    let shrunk_ais = lock_with_error!(shrunk.direction_ais);
+    assert_eq!(shrunk_ais.len(), 2);
+    assert_eq!(network_json(&shrunk_ais[1]), network_json(&saved_ais[1]));
+    remove_files(name);
+}
@@ -0,0 +1,109 @@
+use std::{
+    collections::HashSet,
+    env,
+    num::NonZero,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, ThreadId},
+    time::Instant,
+};
+
+use cannon_ai::{
+    config::ExperimentConfig,
+    lock_with_error,
+    multi_threading::{PopulationFiles, SharedResources},
+    trainer,
+    worker_pool::{Job, WorkerPool},
+};
+
+fn record_threads(pool: &WorkerPool, total_jobs: usize) -> (usize, HashSet<ThreadId>) {
+    let finished = Arc::new(AtomicUsize::new(0));
+    let thread_ids = Arc::new(Mutex::new(HashSet::new()));
+    let jobs = (0..total_jobs)
+        .map(|_| {
+            let finished = Arc::clone(&finished);
+            let thread_ids = Arc::clone(&thread_ids);
+            Box::new(move || {
+                lock_with_error!(thread_ids).insert(thread::current().id());
+                finished.fetch_add(1, Ordering::SeqCst);
+            }) as Job
+        })
+        .collect();
+    pool.run_all(jobs);
+    let thread_ids = lock_with_error!(thread_ids).clone();
+    (finished.load(Ordering::SeqCst), thread_ids)
+}
+
+#[test]
+fn runs_every_job_on_a_fixed_set_of_threads() {
+    let pool = WorkerPool::new(NonZero::new(3).unwrap());
+    let (first_finished, first_threads) = record_threads(&pool, 500);
+    let (second_finished, second_threads) = record_threads(&pool, 500);
+
+    assert_eq!((first_finished, second_finished), (500, 500));
+    let all_threads = first_threads
+        .union(&second_threads)
+        .collect::<HashSet<&ThreadId>>();
+    assert!(all_threads.len() <= 3);
+    assert!(!all_threads.contains(&thread::current().id()));
+}
+
+#[test]
+fn survives_a_panicking_job() {
+    let pool = WorkerPool::new(NonZero::new(2).unwrap());
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pool.run_all(vec![Box::new(|| panic!("job failed")) as Job]);
+    }));
+    assert!(result.is_err());
+    assert_eq!(record_threads(&pool, 10).0, 10);
+}
+
+#[test]
+fn plays_a_population_larger_than_the_pool() {
+    let config = ExperimentConfig {
+        population_size: 40,
+        training_time: 1.0,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = SharedResources::new(
+        config,
+        3,
+        None,
+        NonZero::new(2),
+        PopulationFiles::in_directory(&env::temp_dir(), "cannon_ai_pool_missing"),
+    )
+    .unwrap();
+    shared_resources.is_real_time.store(false, Ordering::SeqCst);
+    assert_eq!(usize::from(*shared_resources.total_ais), 40);
+    assert_eq!(shared_resources.worker_pool.size().get(), 2);
+
+    trainer::run_generation(&shared_resources);
+    let episode_scores = lock_with_error!(shared_resources.episode_scores);
+    assert!(episode_scores.iter().all(|scores| scores.len() == 1));
+}
+
+#[test]
+fn real_time_paces_only_the_selected_ai() {
+    let config = ExperimentConfig {
+        population_size: 8,
+        training_time: 1.0,
+        ..ExperimentConfig::default()
+    };
+    let shared_resources = SharedResources::new(
+        config,
+        5,
+        None,
+        NonZero::new(2),
+        PopulationFiles::in_directory(&env::temp_dir(), "cannon_ai_pool_real_time_missing"),
+    )
+    .unwrap();
+    assert!(shared_resources.is_real_time.load(Ordering::SeqCst));
+
+    // Pacing every AI would take four training times with four AIs per worker.
+    let start = Instant::now();
+    trainer::run_generation(&shared_resources);
+    let elapsed = start.elapsed().as_secs_f32();
+    assert!((0.9..2.0).contains(&elapsed), "{elapsed} seconds");
+}